@@ -0,0 +1,460 @@
+use scrypto::prelude::*;
+use crate::barter::{Escroceipt, SwapStatus};
+
+// For bundles where the seller wants price discovery rather than a fixed request. The seller escrows the bundle and gets
+// the usual Trove creator key, bidders lock their bids in against a receipt, and whoever's on top at the end takes it.
+// Outbid bidders can pull their bids back out any time.
+
+// Bidders get one of these per bid - it's what they refund or claim the bundle with.
+#[derive(NonFungibleData, ScryptoSbor, Debug)]
+pub struct BidReceipt {
+    name: String,
+    description: String,
+    key_image_url: Url,
+    auction_component: ComponentAddress,
+    amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct AuctionCreated {
+    component: ComponentAddress,
+    creator_badge: ResourceAddress,
+    bid_resource: ResourceAddress,
+    ends_at: Instant,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct BidPlaced {
+    component: ComponentAddress,
+    bid_id: NonFungibleLocalId,
+    amount: Decimal,
+    ends_at: Instant,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct AuctionSettled {
+    component: ComponentAddress,
+    winning_bid: Option<NonFungibleLocalId>,
+    amount: Decimal,
+}
+
+#[blueprint]
+#[events(AuctionCreated, BidPlaced, AuctionSettled)]
+mod auction {
+
+    enable_package_royalties! {
+        new_auction => Free;
+        place_bid => Free;
+        refund_bid => Free;
+        winner_claim => Free;
+        seller_claim => Free;
+        seller_cancel => Free;
+        get_badge => Free;
+        get_bid_receipt => Free;
+        get_high_bid => Free;
+        get_ends_at => Free;
+        status => Free;
+    }
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+            seller_claim => restrict_to: [admin];
+            seller_cancel => restrict_to: [admin];
+            place_bid => PUBLIC;
+            refund_bid => PUBLIC;
+            winner_claim => PUBLIC;
+            get_badge => PUBLIC;
+            get_bid_receipt => PUBLIC;
+            get_high_bid => PUBLIC;
+            get_ends_at => PUBLIC;
+            status => PUBLIC;
+        }
+    }
+
+    struct EnglishAuction {
+        auction_component: ComponentAddress,
+
+        // the bundle and the seller's key
+        creator_vaults: HashMap<ResourceAddress, Vault>,
+        a_vault_key: ResourceAddress,
+        a_vault_key_id: NonFungibleLocalId,
+
+        // bidding rules
+        bid_resource: ResourceAddress,
+        start_price: Decimal,
+        min_increment: Decimal,
+        ends_at: Instant,
+        // a bid this many seconds or less from the end pushes the end back out to this many seconds from now
+        extension_window: Option<i64>,
+
+        // every bid, keyed by its receipt - outbid vaults are emptied as their bidders refund
+        bids: KeyValueStore<NonFungibleLocalId, Vault>,
+        bid_receipt_manager: ResourceManager,
+        high_bid: Option<(NonFungibleLocalId, Decimal)>,
+
+        status: SwapStatus,
+        seller_claimed: bool,
+        winner_claimed: bool,
+    }
+
+    impl EnglishAuction {
+        pub fn new_auction(
+            // Just a random name that users can give their auctions
+            custom_trade_name: String,
+            // Optional to include a variety of fungibles in the bundle
+            a_tokens: Option<Vec<Bucket>>,
+            // Optional to include a variety of non fungibles in the bundle
+            a_nfts: Option<Vec<Bucket>>,
+            // What bids are paid in
+            bid_resource: ResourceAddress,
+            // The first bid has to be at least this
+            start_price: Decimal,
+            // Every bid after has to beat the current high bid by at least this
+            min_increment: Decimal,
+            // When bidding closes
+            ends_at: Instant,
+            // Optional anti-sniping - bids in the last this-many seconds extend the auction by the same
+            extension_window: Option<i64>,
+        ) -> (Global<EnglishAuction>, NonFungibleBucket) {
+            assert!(a_nfts.is_some() || a_tokens.is_some(), "You need to offer something");
+            assert!(bid_resource.is_fungible(), "Bids have to be fungible");
+            assert!(start_price > Decimal::zero(), "Starting price must be positive");
+            assert!(min_increment > Decimal::zero(), "Minimum increment must be positive");
+            assert!(
+                Clock::current_time_is_strictly_before(ends_at, TimePrecision::Minute),
+                "Auction would end before it starts"
+            );
+            if extension_window.is_some() {
+                assert!(extension_window.unwrap() > 0, "Extension window must be positive");
+            }
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(EnglishAuction::blueprint_id());
+
+            // same bundle handling and 50 asset cap as Barter
+            let mut nfts_offered: Vec<NonFungibleGlobalId> = Vec::new();
+            let mut tokens_offered: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            let mut creator_vaults: HashMap<ResourceAddress, Vault> = HashMap::new();
+            for bucket in a_tokens.unwrap_or(Vec::new()).into_iter() {
+                *tokens_offered
+                    .entry(bucket.resource_address())
+                    .or_insert(Decimal::zero()) += bucket.amount();
+                creator_vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+            for bucket in a_nfts.unwrap_or(Vec::new()).into_iter() {
+                let resource = bucket.resource_address();
+                for nft_id in bucket.as_non_fungible().non_fungible_local_ids().into_iter() {
+                    nfts_offered.push(NonFungibleGlobalId::new(resource, nft_id))
+                }
+                creator_vaults
+                    .entry(resource)
+                    .or_insert_with(|| Vault::new(resource))
+                    .put(bucket)
+            }
+            assert!(
+                nfts_offered.len() + tokens_offered.len() <= 50,
+                "Reached single transaction event limit"
+            );
+
+            let key_custom_label = String::from("TROVE Key: ") + &custom_trade_name;
+
+            let mut tokens_requested: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            tokens_requested.insert(bid_resource, start_price);
+
+            let a_key = ResourceBuilder::new_string_non_fungible::<Escroceipt>(OwnerRole::None)
+                .metadata(metadata! {
+                    roles {
+                        metadata_locker => rule!(deny_all);
+                        metadata_locker_updater => rule!(deny_all);
+                        metadata_setter => rule!(deny_all);
+                        metadata_setter_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => key_custom_label, locked;
+                        "description" => "Your Auction on trove.tools".to_owned(), locked;
+                        "key_image_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                })
+                .mint_roles(mint_roles!(
+                    minter => rule!(deny_all);
+                    minter_updater => rule!(deny_all);
+                ))
+                .burn_roles(burn_roles!(
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                ))
+                .mint_initial_supply(vec![(
+                    "Trove_Creator_Key".try_into().unwrap(),
+                    Escroceipt {
+                        name: "Trove Auction".to_owned(),
+                        description: "This NFT contains details of your Auction on Trove".to_owned(),
+                        key_image_url: Url::of("https://trove.tools/multiple.png"),
+                        swap_component: component_address,
+                        nfts_offered,
+                        tokens_offered,
+                        nfts_requested: Vec::new(),
+                        collections_requested: Vec::new(),
+                        tokens_requested,
+                    },
+                )]);
+
+            let bid_receipt_manager = ResourceBuilder::new_ruid_non_fungible::<BidReceipt>(OwnerRole::None)
+                .metadata(metadata! {
+                    roles {
+                        metadata_locker => rule!(deny_all);
+                        metadata_locker_updater => rule!(deny_all);
+                        metadata_setter => rule!(deny_all);
+                        metadata_setter_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => "TROVE Bid Receipt".to_owned(), locked;
+                        "description" => "Proof of your bid on an Auction on trove.tools".to_owned(), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                })
+                .mint_roles(mint_roles!(
+                    minter => rule!(require(global_caller(component_address)));
+                    minter_updater => rule!(deny_all);
+                ))
+                .burn_roles(burn_roles!(
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                ))
+                .create_with_no_initial_supply();
+
+            let global_key_id = NonFungibleGlobalId::new(
+                a_key.resource_address(),
+                a_key.as_non_fungible().non_fungible_local_id(),
+            );
+
+            Runtime::emit_event(AuctionCreated {
+                component: component_address,
+                creator_badge: a_key.resource_address(),
+                bid_resource,
+                ends_at,
+            });
+
+            let auction_component = Self {
+                auction_component: component_address,
+                creator_vaults,
+                a_vault_key: a_key.resource_address(),
+                a_vault_key_id: a_key.as_non_fungible().non_fungible_local_id(),
+                bid_resource,
+                start_price,
+                min_increment,
+                ends_at,
+                extension_window,
+                bids: KeyValueStore::new(),
+                bid_receipt_manager,
+                high_bid: None,
+                status: SwapStatus::Open,
+                seller_claimed: false,
+                winner_claimed: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .metadata(metadata! (
+                roles {
+                    metadata_setter => rule!(deny_all);
+                    metadata_setter_updater => rule!(deny_all);
+                    metadata_locker => rule!(deny_all);
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name" => "Trove Auction".to_owned(), locked;
+                    "description" => "Find your auction on https://trove.tools".to_owned(), locked;
+                    "tags" => vec!["Auction".to_string()], locked;
+                    "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                }
+            ))
+            .roles(roles!(
+                admin => rule!(require(global_key_id));
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (auction_component, a_key)
+        }
+
+        /// Locks a bid in and hands back its receipt. Has to beat the current high bid by the minimum increment
+        /// (or meet the starting price if it's the first).
+
+        pub fn place_bid(&mut self, payment: Bucket) -> Bucket {
+            assert!(self.status == SwapStatus::Open, "Auction is closed");
+            assert!(
+                Clock::current_time_is_strictly_before(self.ends_at, TimePrecision::Minute),
+                "Auction has ended"
+            );
+            assert!(payment.resource_address() == self.bid_resource, "Wrong bid resource");
+
+            let amount = payment.amount();
+            match self.high_bid.as_ref() {
+                Some((_, high_amount)) => assert!(
+                    amount >= *high_amount + self.min_increment,
+                    "Bid doesn't beat the high bid by enough"
+                ),
+                None => assert!(amount >= self.start_price, "Bid is below the starting price"),
+            }
+
+            // anti-sniping - a late bid gives everyone else the same window to respond
+            if self.extension_window.is_some() {
+                let window = self.extension_window.unwrap();
+                let now = Clock::current_time(TimePrecision::Minute);
+                if !Clock::current_time_is_strictly_before(
+                    self.ends_at.add_seconds(-window).unwrap(),
+                    TimePrecision::Minute,
+                ) {
+                    self.ends_at = now.add_seconds(window).unwrap();
+                }
+            }
+
+            let receipt = self.bid_receipt_manager.mint_ruid_non_fungible(BidReceipt {
+                name: "Trove Bid".to_owned(),
+                description: "This NFT lets you refund your bid, or claim the bundle if you win".to_owned(),
+                key_image_url: Url::of("https://trove.tools/multiple.png"),
+                auction_component: self.auction_component,
+                amount,
+            });
+            let bid_id = receipt.as_non_fungible().non_fungible_local_id();
+
+            self.bids.insert(bid_id.clone(), Vault::with_bucket(payment));
+            self.high_bid = Some((bid_id.clone(), amount));
+
+            Runtime::emit_event(BidPlaced {
+                component: self.auction_component,
+                bid_id,
+                amount,
+                ends_at: self.ends_at,
+            });
+
+            receipt
+        }
+
+        /// Any bid that's been outbid can be taken back out straight away - only the high bid stays locked.
+
+        pub fn refund_bid(&mut self, bid_receipt: Proof) -> Bucket {
+            let bid_id = self.check_bid_receipt(bid_receipt);
+            assert!(
+                self.high_bid.as_ref().map(|(high_id, _)| high_id != &bid_id).unwrap_or(true),
+                "The winning bid can't be refunded"
+            );
+
+            let mut bid_vault = self.bids.get_mut(&bid_id).expect("No such bid");
+            assert!(!bid_vault.is_empty(), "Bid has already been refunded");
+            bid_vault.take_all()
+        }
+
+        /// After the end, the high bidder collects the bundle with their receipt.
+
+        pub fn winner_claim(&mut self, bid_receipt: Proof) -> Vec<Bucket> {
+            let bid_id = self.check_bid_receipt(bid_receipt);
+            self.settle();
+            assert!(
+                self.high_bid.as_ref().map(|(high_id, _)| high_id == &bid_id).unwrap_or(false),
+                "Not the winning bid"
+            );
+            assert!(!self.winner_claimed, "Bundle has already been claimed");
+            self.winner_claimed = true;
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.creator_vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+
+            return buckets;
+        }
+
+        /// After the end, the seller collects the winning bid - or gets their bundle back if nobody bid.
+
+        pub fn seller_claim(&mut self) -> Vec<Bucket> {
+            self.settle();
+            assert!(!self.seller_claimed, "Auction proceeds have already been claimed");
+            self.seller_claimed = true;
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            match self.high_bid.clone() {
+                Some((high_id, _)) => {
+                    let mut bid_vault = self.bids.get_mut(&high_id).unwrap();
+                    buckets.push(bid_vault.take_all())
+                }
+                None => {
+                    for vault in self.creator_vaults.values_mut() {
+                        buckets.push(vault.take_all())
+                    }
+                }
+            }
+
+            return buckets;
+        }
+
+        /// The seller can only back out before anyone has bid.
+
+        pub fn seller_cancel(&mut self) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Open, "Auction is closed");
+            assert!(self.high_bid.is_none(), "Auction already has bids");
+            self.status = SwapStatus::Cancelled;
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.creator_vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+
+            return buckets;
+        }
+
+        // creator key resource and local id
+        pub fn get_badge(&self) -> (ResourceAddress, NonFungibleLocalId) {
+            (self.a_vault_key, self.a_vault_key_id.clone())
+        }
+
+        pub fn get_bid_receipt(&self) -> ResourceAddress {
+            self.bid_receipt_manager.address()
+        }
+
+        pub fn get_high_bid(&self) -> Option<(NonFungibleLocalId, Decimal)> {
+            self.high_bid.clone()
+        }
+
+        // moves with every anti-sniping extension
+        pub fn get_ends_at(&self) -> Instant {
+            self.ends_at
+        }
+
+        pub fn status(&self) -> SwapStatus {
+            self.status.clone()
+        }
+
+        // closes the auction the first time either side claims after the end
+        fn settle(&mut self) {
+            assert!(
+                Clock::current_time_is_at_or_after(self.ends_at, TimePrecision::Minute),
+                "Auction hasn't ended yet"
+            );
+            if self.status != SwapStatus::Open {
+                assert!(self.status == SwapStatus::Settled, "Auction was cancelled");
+                return;
+            }
+            self.status = SwapStatus::Settled;
+
+            Runtime::emit_event(AuctionSettled {
+                component: self.auction_component,
+                winning_bid: self.high_bid.as_ref().map(|(high_id, _)| high_id.clone()),
+                amount: self.high_bid.as_ref().map(|(_, amount)| *amount).unwrap_or(Decimal::zero()),
+            });
+        }
+
+        fn check_bid_receipt(&self, bid_receipt: Proof) -> NonFungibleLocalId {
+            bid_receipt
+                .check(self.bid_receipt_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
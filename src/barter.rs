@@ -1,19 +1,203 @@
 use scrypto::prelude::*;
+use crate::registry::registry::TroveRegistry;
+use crate::registry::FeeConfig;
 
 /// Hello Beem, welcome to my blueprint. Make yourself at home, but don't touch anything you can't afford.
 
 // Each Swap Creator gets give a Swap Key - this is the non-fungible struct for that key
 // I don't actually use all this data thats being stored on it on Trove, but it does mean there's an easy access full-record.
+// Auctions and Dutch listings hand their sellers the same key, with the payment resource and starting price as what's requested.
 #[derive(NonFungibleData, ScryptoSbor, Debug)]
 pub struct Escroceipt {
+    pub name: String,
+    pub description: String,
+    pub key_image_url: Url,
+    pub swap_component: ComponentAddress,
+    pub nfts_offered: Vec<NonFungibleGlobalId>,
+    pub tokens_offered: HashMap<ResourceAddress, Decimal>,
+    pub nfts_requested: Vec<NonFungibleGlobalId>,
+    pub collections_requested: Vec<CollectionRequest>,
+    pub tokens_requested: HashMap<ResourceAddress, Decimal>,
+}
+
+// For when you don't care which NFTs you get back, just how many from a collection - e.g. "any 2 Cars".
+// Add some traits and it becomes "any 2 Cars made by Ford" - every trait has to match the NFT's on-ledger data.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct CollectionRequest {
+    pub resource: ResourceAddress,
+    pub amount: u64,
+    pub traits: Vec<TraitPredicate>,
+}
+
+// A single field check against an NFT's data. `field` is the position of the field in the collection's NonFungibleData
+// struct, as that's all that's on-ledger - e.g. field 1 is `manufacturer` on the bootstrap Car, Phone and Laptop NFTs.
+// Only string fields can be matched for now.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct TraitPredicate {
+    field: u32,
+    value: String,
+}
+
+// Restricts the partner side to holders of a resource instead of named accounts - e.g. "only holders of our DAO NFT
+// can take this deal". The partner presents a proof of at least `amount` of `resource` wherever a partner badge is asked for.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct HolderGate {
+    resource: ResourceAddress,
+    amount: Decimal,
+}
+
+// Where a counter offer is at - takers can pull a pending or rejected counter offer back out at any time,
+// an accepted one lets them collect the creator's assets instead.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub enum CounterStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Claimed,
+    Withdrawn,
+}
+
+// A taker's alternative bundle, escrowed until the creator makes their mind up.
+#[derive(ScryptoSbor)]
+pub struct CounterOffer {
+    vaults: HashMap<ResourceAddress, Vault>,
+    nfts_offered: Vec<NonFungibleGlobalId>,
+    tokens_offered: HashMap<ResourceAddress, Decimal>,
+    partner_key: Option<NonFungibleLocalId>,
+    referral: Option<Referral>,
+    status: CounterStatus,
+}
+
+// A neutral third party for high-value deals. `account` is sent an arbiter key, and for `dispute_window` seconds after
+// the partner side is fully funded only the arbiter can settle the swap or unwind it - after that it settles as normal.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct Arbitration {
+    account: ComponentAddress,
+    dispute_window: i64,
+}
+
+// A frontend's cut of the platform fee for bringing one side of the swap in - `share_bps` of the fee that side's
+// claim pays, capped at the registry's `max_referral_bps`.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct Referral {
+    pub account: ComponentAddress,
+    pub share_bps: u16,
+}
+
+// Filled in by the TroveRegistry when it creates a swap on a user's behalf - the registry to report status changes to,
+// and its fee config at the time. The swap keeps that fee config for good, so a later change on the registry
+// can't reach swaps that were already made.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct RegistryLink {
+    pub component: ComponentAddress,
+    pub fee_config: Option<FeeConfig>,
+}
+
+// Everything optional about how a swap trades, in one place - start from `ProposalOptions::default()` (a public swap
+// with no window, gate, approval, arbiter, delay or referral) and set what you need.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ProposalOptions {
+    // Optional to restrict the swap to holders of a resource instead of named partners
+    pub holder_gate: Option<HolderGate>,
+    // Optional time before which the swap can't be taken
+    pub valid_from: Option<Instant>,
+    // Optional time after which the swap can't be taken - anyone can then call `expire` on it
+    pub expires_at: Option<Instant>,
+    // Whether the creator has to approve the partner's deposit before it can be claimed
+    pub require_approval: bool,
+    // Optional arbiter who can settle or unwind the swap for a while after it's funded
+    pub arbiter: Option<Arbitration>,
+    // Optional number of seconds a funded swap stays locked before either side can claim
+    pub settlement_delay: Option<i64>,
+    // Optional frontend that gets a cut of the fee the creator pays
+    pub referral: Option<Referral>,
+}
+
+// Where the swap is at. Drafts are still being filled in by the creator, Open swaps can be taken, and PartnerFunded
+// means someone has started funding the partner side and holds it with their deposit receipt until they claim or pull out.
+// Swaps that need the creator's approval sit in PendingApproval once fully funded, until the creator approves or rejects.
+// Settled, Cancelled and Expired are final - all that's left is for each side to collect what they're owed.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub enum SwapStatus {
+    Draft,
+    Open,
+    PartnerFunded,
+    PendingApproval,
+    Approved,
+    Settled,
+    Cancelled,
+    Expired,
+}
+
+// The partner side's deposit - who holds it, and which halves of the request it covers so far. Both halves can come
+// in over several calls, so what's been received so far is kept to check each batch against the rest.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PartnerDeposit {
+    receipt: NonFungibleLocalId,
+    nfts: bool,
+    tokens: bool,
+    nfts_received: Vec<NonFungibleGlobalId>,
+    tokens_received: HashMap<ResourceAddress, Decimal>,
+    // the partner key the first deposit was made with, for `filled_by` if the arbiter ends up settling the swap
+    partner_key: Option<NonFungibleLocalId>,
+}
+
+// Everything there is to know about a swap in one go, for indexers and frontends. Balances are what's actually
+// sitting in the vaults right now (an NFT counts as 1), so they'll drop to zero as each side is claimed.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct ProposalView {
+    pub component: ComponentAddress,
+    pub status: SwapStatus,
+    pub created_at: Instant,
+    pub valid_from: Option<Instant>,
+    pub expires_at: Option<Instant>,
+    pub require_approval: bool,
+    pub arbiter: Option<ComponentAddress>,
+    pub settlement_delay: Option<i64>,
+    pub funded_at: Option<Instant>,
+    pub disputed: bool,
+    pub creator_badge: NonFungibleGlobalId,
+    pub nfts_offered: Vec<NonFungibleGlobalId>,
+    pub offered_balances: HashMap<ResourceAddress, Decimal>,
+    pub nfts_requested: Vec<NonFungibleGlobalId>,
+    pub collections_requested: Vec<CollectionRequest>,
+    pub tokens_requested: HashMap<ResourceAddress, Decimal>,
+    pub partner_badge: Option<ResourceAddress>,
+    pub partner_keys: Vec<NonFungibleLocalId>,
+    pub holder_gate: Option<HolderGate>,
+    pub filled_by: Option<NonFungibleLocalId>,
+    pub partner_deposit: Option<PartnerDeposit>,
+    pub partner_balances: HashMap<ResourceAddress, Decimal>,
+}
+
+// Partners get one of these when they deposit - it's what lets them (and only them) claim the creator's side or pull
+// their deposit back out. Stops anyone else sniping a public swap that's been funded but not yet claimed.
+#[derive(NonFungibleData, ScryptoSbor, Debug)]
+pub struct DepositReceipt {
     name: String,
     description: String,
     key_image_url: Url,
     swap_component: ComponentAddress,
-    nfts_offered: Vec<NonFungibleGlobalId>,
-    tokens_offered: HashMap<ResourceAddress, Decimal>,
-    nfts_requested: Vec<NonFungibleGlobalId>,
-    tokens_requested: HashMap<ResourceAddress, Decimal>,
+}
+
+// Who counts as the owner of an account when it comes to picking up a key it refused - whoever signs with one of
+// its `owner_keys`, or once it's been securified, whoever can produce its owner badge. Accounts created with some
+// other owner rule (e.g. allocated by a dApp) match neither, which is what the creator-side reclaim is for.
+pub fn account_owner_rule(account: ComponentAddress) -> AccessRule {
+    let owner_keys: Vec<PublicKeyHash> = Global::<Account>::from(account)
+        .get_metadata("owner_keys")
+        .ok()
+        .flatten()
+        .unwrap_or(Vec::new());
+    let owner_badges: Vec<NonFungibleGlobalId> = owner_keys
+        .into_iter()
+        .map(|key| NonFungibleGlobalId::from_public_key_hash(key))
+        .collect();
+    let securified_badge = NonFungibleGlobalId::new(
+        ACCOUNT_OWNER_BADGE,
+        NonFungibleLocalId::bytes(account.as_node_id().0).unwrap(),
+    );
+    rule!(require_any_of(owner_badges) || require(securified_badge))
 }
 
 // Just some events for the front end to nab data from.
@@ -31,44 +215,186 @@ struct PartnerLocked {
     partner_local_id: NonFungibleLocalId,
 }
 
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ProposalExpired {
+    component: ComponentAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct CounterOfferProposed {
+    component: ComponentAddress,
+    counter_id: NonFungibleLocalId,
+    nfts_offered: Vec<NonFungibleGlobalId>,
+    tokens_offered: HashMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct CounterOfferDecided {
+    component: ComponentAddress,
+    counter_id: NonFungibleLocalId,
+    accepted: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ProposalPublished {
+    component: ComponentAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct RoyaltyPaid {
+    component: ComponentAddress,
+    collection: ResourceAddress,
+    resource: ResourceAddress,
+    amount: Decimal,
+    recipient: ComponentAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct FeeCharged {
+    component: ComponentAddress,
+    resource: ResourceAddress,
+    amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ReferralPaid {
+    component: ComponentAddress,
+    referrer: ComponentAddress,
+    resource: ResourceAddress,
+    amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ArbiterDecided {
+    component: ComponentAddress,
+    released: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct DisputeRaised {
+    component: ComponentAddress,
+    by_creator: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct StatusChanged {
+    component: ComponentAddress,
+    from: SwapStatus,
+    to: SwapStatus,
+}
+
 #[blueprint]
-#[events(ComponentCreated, PartnerLocked)]
+#[events(ComponentCreated, PartnerLocked, ProposalExpired, CounterOfferProposed, CounterOfferDecided, ProposalPublished, StatusChanged, FeeCharged, RoyaltyPaid, ReferralPaid, ArbiterDecided, DisputeRaised)]
 mod barter {
 
     // Potential bug here, should cost users $1k USD to swap IMO.
+    // Claims used to carry a flat 20 XRD royalty - the registry's fee config is what swaps pay now, sized to the trade.
     enable_package_royalties! {
         new_trade_proposal => Free;
+        new_draft => Free;
         partner_deposit_tokens => Free;
         partner_deposit_nfts => Free;
-        partner_claims_creator_assets => Xrd(20.into());
-        creator_claims_partner_assets => Xrd(20.into());
+        partner_claims_creator_assets => Free;
+        accept_trade => Free;
+        creator_claims_partner_assets => Free;
         creator_cancel => Free;
+        creator_cancel_page => Free;
         partner_cancel => Free;
+        partner_cancel_page => Free;
         burn_creator_badge => Free;
         get_badge => Free;
+        get_deposit_receipt => Free;
         burn_partner_badge => Free;
+        burn_deposit_receipt => Free;
+        expire => Free;
+        propose_counter_offer => Free;
+        creator_accept_counter => Free;
+        creator_reject_counter => Free;
+        partner_claim_counter => Free;
+        get_counter_offers => Free;
+        partner_claim_key => Free;
+        creator_reclaim_partner_key => Free;
+        add_offer_tokens => Free;
+        add_offer_nfts => Free;
+        add_request_nfts => Free;
+        add_request_collections => Free;
+        add_request_tokens => Free;
+        publish => Free;
+        status => Free;
+        get_proposal => Free;
+        ring_release => Free;
+        ring_fill => Free;
+        withdraw_fees => Free;
+        partner_claim_page => Free;
+        creator_claim_page => Free;
+        creator_approve => Free;
+        creator_reject => Free;
+        partner_claim_refund => Free;
+        partner_claim_refund_page => Free;
+        arbiter_release => Free;
+        arbiter_refund => Free;
+        raise_dispute => Free;
     }
 
     enable_method_auth! {
         roles {
             admin => updatable_by: [];
+            ring => updatable_by: [];
+            fee_collector => updatable_by: [];
+            arbiter => updatable_by: [];
         },
         methods {
             creator_cancel => restrict_to: [admin];
+            creator_cancel_page => restrict_to: [admin];
             creator_claims_partner_assets => restrict_to: [admin];
+            creator_accept_counter => restrict_to: [admin];
+            creator_reject_counter => restrict_to: [admin];
+            add_offer_tokens => restrict_to: [admin];
+            add_offer_nfts => restrict_to: [admin];
+            add_request_nfts => restrict_to: [admin];
+            add_request_collections => restrict_to: [admin];
+            add_request_tokens => restrict_to: [admin];
+            publish => restrict_to: [admin];
+            creator_claim_page => restrict_to: [admin];
+            creator_approve => restrict_to: [admin];
+            creator_reject => restrict_to: [admin];
+            ring_release => restrict_to: [ring];
+            ring_fill => restrict_to: [ring];
+            withdraw_fees => restrict_to: [fee_collector];
+            arbiter_release => restrict_to: [arbiter];
+            arbiter_refund => restrict_to: [arbiter];
             burn_creator_badge => PUBLIC;
             partner_deposit_tokens => PUBLIC;
             partner_deposit_nfts => PUBLIC;
             partner_claims_creator_assets => PUBLIC;
+            accept_trade => PUBLIC;
             partner_cancel => PUBLIC;
+            partner_cancel_page => PUBLIC;
             get_badge => PUBLIC;
+            get_deposit_receipt => PUBLIC;
             burn_partner_badge => PUBLIC;
+            burn_deposit_receipt => PUBLIC;
+            expire => PUBLIC;
+            propose_counter_offer => PUBLIC;
+            partner_claim_counter => PUBLIC;
+            get_counter_offers => PUBLIC;
+            partner_claim_key => PUBLIC;
+            creator_reclaim_partner_key => restrict_to: [admin];
+            partner_claim_page => PUBLIC;
+            status => PUBLIC;
+            get_proposal => PUBLIC;
+            partner_claim_refund => PUBLIC;
+            partner_claim_refund_page => PUBLIC;
+            raise_dispute => PUBLIC;
         }
     }
 
     struct Barter {
+        swap_component: ComponentAddress,
+
         // creator assets and badge
         creator_vaults: HashMap<ResourceAddress, Vault>,
+        nfts_offered: Vec<NonFungibleGlobalId>,
         a_vault_key: ResourceAddress,
         a_vault_key_id: NonFungibleLocalId,
         a_vault_key_global: NonFungibleGlobalId,
@@ -76,20 +402,55 @@ mod barter {
         // partner assets and badge (optional)
         partner_vaults: HashMap<ResourceAddress, Vault>,
         badge_partner: Option<ResourceAddress>,
-        badge_partner_local: Option<NonFungibleLocalId>,
+        badge_partner_locals: Vec<NonFungibleLocalId>,
+        // partner (or arbiter) keys the account wouldn't accept, waiting to be picked up
+        unclaimed_partner_keys: KeyValueStore<ComponentAddress, Vault>,
+        // which partner key actually took the swap
+        filled_by: Option<NonFungibleLocalId>,
+        // or, rather than named partners, anyone holding this
+        holder_gate: Option<HolderGate>,
+        // whether the creator has to approve whoever funds the partner side before they can claim
+        require_approval: bool,
+        // a cancelled or turned down deposit still sitting in partner_vaults, paged back out to whoever holds this receipt.
+        // Nobody else can fund the swap until it's all gone
+        pending_withdrawal: Option<NonFungibleLocalId>,
+        // the arbiter's dispute window and the settlement delay both start once the partner side is fully funded
+        arbiter: Option<Arbitration>,
+        settlement_delay: Option<i64>,
+        funded_at: Option<Instant>,
+        // raised by either side during the delay - holds the swap back from settling
+        disputed: bool,
 
         // state record of expected assets from partner
         expected_nfts: Vec<NonFungibleGlobalId>,
+        expected_collections: Vec<CollectionRequest>,
         expected_tokens: HashMap<ResourceAddress, Decimal>,
 
+        // counter offers from takers, keyed by the receipt they were given
+        counter_offers: KeyValueStore<NonFungibleLocalId, CounterOffer>,
+        counter_offer_ids: Vec<NonFungibleLocalId>,
+
+        // optional window the swap can be taken in
+        valid_from: Option<Instant>,
+        expires_at: Option<Instant>,
+
+        // the registry indexing this swap, if it was created through one, and its fee config as it was then
+        registry: Option<ComponentAddress>,
+        fee_config: Option<FeeConfig>,
+        fee_vaults: HashMap<ResourceAddress, Vault>,
+        // the frontends that brought each side in, paid a cut of the fee that side's claim pays
+        creator_referral: Option<Referral>,
+        partner_referral: Option<Referral>,
+
         // resource manager for burning badges
         proposal_resource_manager: ResourceManager,
 
-        // state bools for validation
-        tokens_validated: bool,
-        nfts_validated: bool,
-        private: bool,
-        swapped: bool,
+        // deposit receipts handed to whoever funds the partner side, and the one currently holding the deposit
+        deposit_receipt_manager: ResourceManager,
+        partner_deposit: Option<PartnerDeposit>,
+
+        status: SwapStatus,
+        created_at: Instant,
         category: Option<ResourceAddress>,
     }
 
@@ -97,20 +458,108 @@ mod barter {
         pub fn new_trade_proposal(
             // Just a random name that users can give their swaps
             custom_trade_name: String,
-            // Optional to add a partner by including their account address - this triggers various access controls,
-            // including an 'anti-pattern' airdrop deposit of a badge to the partner address automatically.
-            partner: Option<ComponentAddress>,
+            // Optional to add partners by including their account addresses - this triggers various access controls,
+            // including an 'anti-pattern' airdrop deposit of a badge to each partner address automatically.
+            partners: Option<Vec<ComponentAddress>>,
+            // Optional to include a variety of fungibles in your offer
+            a_tokens: Option<Vec<Bucket>>,
+            // Optional to include a variety of non fungibles in your offer
+            a_nfts: Option<Vec<Bucket>>,
+            // Optional to include a list of non fungible assets you're requesting in return
+            b_nft_deposits: Option<Vec<NonFungibleGlobalId>>,
+            // Optional to request any number of NFTs from a collection, rather than specific ones
+            b_collection_deposits: Option<Vec<CollectionRequest>>,
+            // Optional to include a list of fungible assets you'ree requesting in retunr
+            b_token_deposits: Option<HashMap<ResourceAddress, Decimal>>,
+            // Set by the TroveRegistry when it creates the swap on a user's behalf - can't be set by anyone else
+            registry: Option<RegistryLink>,
+            // Trading window, holder gate, approval, arbiter, settlement delay and referral
+            options: ProposalOptions,
+            // return component address, creator's badge resource address + local id, partners badge (optional) and the creator's badge itself
+        ) -> (Global<Barter>, NonFungibleBucket) {
+            //There is an oversight here though..... I should really check that something is actually being requested or offered.
+            //I set it up on the frontend, but I should really do it here too.
+
+            if (b_nft_deposits.is_none() && b_collection_deposits.is_none() && b_token_deposits.is_none())
+                || (a_nfts.is_none() && a_tokens.is_none())
+            {
+                panic!("You need to offer/request something")
+            }
+
+            Self::instantiate_barter(
+                custom_trade_name,
+                partners,
+                a_tokens,
+                a_nfts,
+                b_nft_deposits,
+                b_collection_deposits,
+                b_token_deposits,
+                registry,
+                options,
+                false,
+            )
+        }
+
+        /// For bundles too big to fit in one transaction. Creates an empty swap in Draft mode which the creator fills
+        /// with the `add_offer_*`/`add_request_*` methods over as many transactions as they need, then `publish`es.
+        /// Nothing on the partner side works until it's published.
+
+        pub fn new_draft(
+            custom_trade_name: String,
+            partners: Option<Vec<ComponentAddress>>,
+            registry: Option<RegistryLink>,
+            options: ProposalOptions,
+        ) -> (Global<Barter>, NonFungibleBucket) {
+            Self::instantiate_barter(
+                custom_trade_name,
+                partners,
+                None,
+                None,
+                None,
+                None,
+                None,
+                registry,
+                options,
+                true,
+            )
+        }
+
+        fn instantiate_barter(
+            // Just a random name that users can give their swaps
+            custom_trade_name: String,
+            // Optional to add partners by including their account addresses - this triggers various access controls,
+            // including an 'anti-pattern' airdrop deposit of a badge to each partner address automatically.
+            partners: Option<Vec<ComponentAddress>>,
             // Optional to include a variety of fungibles in your offer
             a_tokens: Option<Vec<Bucket>>,
             // Optional to include a variety of non fungibles in your offer
             a_nfts: Option<Vec<Bucket>>,
             // Optional to include a list of non fungible assets you're requesting in return
             b_nft_deposits: Option<Vec<NonFungibleGlobalId>>,
+            // Optional to request any number of NFTs from a collection, rather than specific ones
+            b_collection_deposits: Option<Vec<CollectionRequest>>,
             // Optional to include a list of fungible assets you'ree requesting in retunr
             b_token_deposits: Option<HashMap<ResourceAddress, Decimal>>,
+            registry: Option<RegistryLink>,
+            options: ProposalOptions,
+            // drafts start empty and are filled in later
+            draft: bool,
             // return component address, creator's badge resource address + local id, partners badge (optional) and the creator's badge itself
         ) -> (Global<Barter>, NonFungibleBucket) {
+            let ProposalOptions {
+                holder_gate,
+                valid_from,
+                expires_at,
+                require_approval,
+                arbiter,
+                settlement_delay,
+                referral,
+            } = options;
+            let fee_config = registry.as_ref().and_then(|link| link.fee_config.clone());
+            let registry = registry.map(|link| link.component);
+
             let mut b_nft_deposits_unwrap: Vec<NonFungibleGlobalId> = Vec::new();
+            let mut b_collection_deposits_unwrap: Vec<CollectionRequest> = Vec::new();
             let mut b_token_deposits_unwrap: HashMap<ResourceAddress, Decimal> = HashMap::new();
 
             // all of this section is calculating how many unique assets have been included in the swap proposal
@@ -120,27 +569,71 @@ mod barter {
             // Also I have no idea how to handle if the NFTs themselves have tonnes of data on them which could also potentially screw
             // the total amount of assets that can be exchanged... in anycase there's a failssafe method for the creator
             // to withdraw their assets once the swap's created.
+            // Anything bigger than this has to be built up over several transactions with `new_draft`.
 
-            //There is an oversight here though..... I should really check that something is actually being requested or offered.
-            //I set it up on the frontend, but I should really do it here too.
 
-            if (b_nft_deposits.is_none() && b_token_deposits.is_none())
-                || (a_nfts.is_none() && a_tokens.is_none())
-            {
-                panic!("You need to offer/request something")
+            if valid_from.is_some() && expires_at.is_some() {
+                assert!(
+                    valid_from.unwrap().seconds_since_unix_epoch
+                        < expires_at.unwrap().seconds_since_unix_epoch,
+                    "Swap would expire before it opens"
+                );
             }
 
-           
+            Self::check_referral(&referral);
+
+            // only the registry itself can sign a swap up to it - otherwise anyone could point a swap at a component
+            // that refuses the status callbacks and lock the partner's deposit in
+            if registry.is_some() {
+                Runtime::assert_access_rule(rule!(
+                    require(package_of_direct_caller(Runtime::package_address()))
+                        && require(global_caller(registry.unwrap()))
+                ));
+            }
+
+            let partner_accounts: Vec<ComponentAddress> = partners.unwrap_or(Vec::new());
+            for (index, account_address) in partner_accounts.iter().enumerate() {
+                assert!(
+                    !partner_accounts[..index].contains(account_address),
+                    "Duplicate partner account"
+                );
+            }
+            assert!(
+                partner_accounts.is_empty() || holder_gate.is_none(),
+                "Choose either partners or a holder gate, not both"
+            );
+            if holder_gate.is_some() {
+                assert!(
+                    holder_gate.as_ref().unwrap().amount > Decimal::zero(),
+                    "Holder gate amount must be positive"
+                );
+            }
+            if arbiter.is_some() {
+                let arbitration = arbiter.as_ref().unwrap();
+                assert!(arbitration.dispute_window > 0, "Dispute window must be positive");
+                assert!(
+                    !partner_accounts.contains(&arbitration.account),
+                    "Arbiter can't be one of the partners"
+                );
+            }
+            if settlement_delay.is_some() {
+                assert!(settlement_delay.unwrap() > 0, "Settlement delay must be positive");
+            }
 
             let mut a_nft_len = 0;
             let mut a_tokens_len = 0;
             let mut b_nft_len = 0;
+            let mut b_collection_len: u64 = 0;
             let mut b_tokens_len = 0;
 
             if b_nft_deposits.is_some() {
                 b_nft_len = b_nft_deposits.as_ref().unwrap().len();
                 b_nft_deposits_unwrap = b_nft_deposits.unwrap();
             }
+            if b_collection_deposits.is_some() {
+                b_collection_deposits_unwrap = b_collection_deposits.unwrap();
+                b_collection_len = Self::collection_count(&b_collection_deposits_unwrap);
+            }
             if b_token_deposits.is_some() {
                 b_tokens_len = b_token_deposits.as_ref().unwrap().len();
                 b_token_deposits_unwrap = b_token_deposits.unwrap();
@@ -157,7 +650,8 @@ mod barter {
             }
 
             assert!(
-                b_nft_len + b_tokens_len + a_tokens_len + a_nft_len <= 50,
+                b_collection_len <= 50
+                    && b_nft_len + b_collection_len as usize + b_tokens_len + a_tokens_len + a_nft_len + partner_accounts.len() <= 50,
                 "Reached single transaction event limit"
             );
 
@@ -226,9 +720,8 @@ mod barter {
             let expected_b_nft_deposits = b_nft_deposits_unwrap.clone();
             let expected_b_token_deposits = b_token_deposits_unwrap.clone();
 
-            let mut private_bool = false;
             let mut badge_option = None as Option<ResourceAddress>;
-            let mut badge_local_id = None as Option<NonFungibleLocalId>;
+            let mut badge_local_ids: Vec<NonFungibleLocalId> = Vec::new();
 
             let key_custom_name = String::from("TROVE Key: ");
 
@@ -241,52 +734,39 @@ mod barter {
             // This is perhaps over complicated - but basically if you set the swap to private - which you'd only really need to do
             // if you were only requesting a fungible token and you were doing a 'good deal' for someone.
             // If you were requesting an NFT, it doesn't really matter as only one account can have that NFT.
-            // The way I handle this here to offer so conditional logic that if private swap is ticked, then a creator key and partner
-            // key are minted. If not, then just a creator key is minted.
+            // A creator key is always minted, and if the swap is private then one partner key is minted alongside it for each
+            // partner account (Trove_Partner_Key_1, Trove_Partner_Key_2...) - any one of them can take the swap.
+
+            let escroceipt = |description: &str| Escroceipt {
+                name: "Trove Swap".to_owned(),
+                description: description.to_owned(),
+                key_image_url: Url::of("https://trove.tools/multiple.png"),
+                swap_component: component_address.clone(),
+                nfts_offered: a_nft_deposits.clone(),
+                tokens_offered: a_token_deposits.clone(),
+                nfts_requested: b_nft_deposits_unwrap.clone(),
+                collections_requested: b_collection_deposits_unwrap.clone(),
+                tokens_requested: b_token_deposits_unwrap.clone(),
+            };
 
-            if !partner.is_some() {
-                a_key = ResourceBuilder::new_string_non_fungible::<Escroceipt>(OwnerRole::None)
-                .metadata(metadata! {
-                    roles {
-                        metadata_locker => rule!(deny_all);
-                        metadata_locker_updater => rule!(deny_all);
-                        metadata_setter => rule!(deny_all);
-                        metadata_setter_updater => rule!(deny_all);
-                    },
-                    init {
-                        "name" => key_name_clone.to_owned(), locked;
-                        "description" => "Your Swap Proposal on trove.tools".to_owned(), locked;
-                        "key_image_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
-                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
-                    }
-                })
-                .mint_roles(mint_roles!(
-                    minter => rule!(deny_all);
-                    minter_updater => rule!(deny_all);
-                ))
-                .burn_roles(burn_roles!(
-                    burner => rule!(require(global_caller(component_address)));
-                    burner_updater => rule!(deny_all);
-                ))
-                .non_fungible_data_update_roles(non_fungible_data_update_roles!(
-                    non_fungible_data_updater => rule!(deny_all);
-                    non_fungible_data_updater_updater => rule!(deny_all);
-                ))
-                .mint_initial_supply([("Trove_Creator_Key".try_into().unwrap(),
-                     Escroceipt {
-                    name: "Trove Swap".to_owned(),
-                    description: "This NFT contains details of your Swap on Trove".to_owned(),
-                    key_image_url: Url::of("https://trove.tools/multiple.png"),
-                    swap_component: component_address.clone(),
-                    nfts_offered: a_nft_deposits.clone(),
-                    tokens_offered: a_token_deposits.clone(),
-                    nfts_requested: b_nft_deposits_unwrap.clone(),
-                    tokens_requested: b_token_deposits_unwrap.clone(),
-                })]);
-            } else {
-                let account_address = partner.unwrap();
+            let mut keys: Vec<(StringNonFungibleLocalId, Escroceipt)> = vec![(
+                "Trove_Creator_Key".try_into().unwrap(),
+                escroceipt("This NFT contains details of your Swap on Trove"),
+            )];
+            for (index, _) in partner_accounts.iter().enumerate() {
+                keys.push((
+                    StringNonFungibleLocalId::new(format!("Trove_Partner_Key_{}", index + 1)).unwrap(),
+                    escroceipt("This NFT's metadata contains details of the requested Swap on Trove"),
+                ));
+            }
+            if arbiter.is_some() {
+                keys.push((
+                    "Trove_Arbiter_Key".try_into().unwrap(),
+                    escroceipt("This NFT lets you settle or unwind a disputed Swap on Trove"),
+                ));
+            }
 
-                a_key = ResourceBuilder::new_string_non_fungible::<Escroceipt>(OwnerRole::None)
+            a_key = ResourceBuilder::new_string_non_fungible::<Escroceipt>(OwnerRole::None)
                 .metadata(metadata! {
                     roles {
                         metadata_locker => rule!(deny_all);
@@ -313,51 +793,62 @@ mod barter {
                     non_fungible_data_updater => rule!(deny_all);
                     non_fungible_data_updater_updater => rule!(deny_all);
                 ))
-                .mint_initial_supply([("Trove_Creator_Key".try_into().unwrap(),
-                     Escroceipt {
-                    name: "Trove Swap".to_owned(),
-                    description: "This NFT contains details of your Swap on Trove".to_owned(),
-                    key_image_url: Url::of("https://trove.tools/multiple.png"),
-                    swap_component: component_address.clone(),
-                    nfts_offered: a_nft_deposits.clone(),
-                    tokens_offered: a_token_deposits.clone(),
-                    nfts_requested: b_nft_deposits_unwrap.clone(),
-                    tokens_requested: b_token_deposits_unwrap.clone(),
-                }), ("Trove_Partner_Key".try_into().unwrap(),
-                Escroceipt {
-               name: "Trove Swap".to_owned(),
-               description: "This NFT's metadata contains details of the requested Swap on Trove".to_owned(),
-               key_image_url: Url::of("https://trove.tools/multiple.png"),
-               swap_component: component_address.clone(),
-               nfts_offered: a_nft_deposits.clone(),
-               tokens_offered: a_token_deposits.clone(),
-               nfts_requested: b_nft_deposits_unwrap.clone(),
-               tokens_requested: b_token_deposits_unwrap.clone(),
-           })
-                ]);
-                let partner_local_quick_id =
-                    NonFungibleLocalId::String("Trove_Partner_Key".try_into().unwrap());
+                .mint_initial_supply(keys);
+
+            let unclaimed_partner_keys: KeyValueStore<ComponentAddress, Vault> = KeyValueStore::new();
+
+            for (index, account_address) in partner_accounts.into_iter().enumerate() {
+                let partner_local_quick_id = NonFungibleLocalId::String(
+                    StringNonFungibleLocalId::new(format!("Trove_Partner_Key_{}", index + 1)).unwrap(),
+                );
                 let partner_nft_badge = a_key.take_non_fungible(&partner_local_quick_id);
 
                 let partner_nft_badge_resource = partner_nft_badge.resource_address();
-                let partner_nft_badge_local =
-                    partner_nft_badge.as_non_fungible().non_fungible_local_id();
                 let badge_bucket: Vec<Bucket> = vec![partner_nft_badge.into()];
 
-                Global::<Account>::from(account_address)
-                    .try_deposit_batch_or_abort(badge_bucket, None);
-                
-                private_bool = true;
+                // accounts can be set to refuse resources they've not seen before - if so, the key is held here
+                // until the partner comes and picks it up with `partner_claim_key`
+                let refused_keys = Global::<Account>::from(account_address)
+                    .try_deposit_batch_or_refund(badge_bucket, None);
+                if let Some(refused_keys) = refused_keys {
+                    let mut key_vault = Vault::new(partner_nft_badge_resource);
+                    for key in refused_keys.into_iter() {
+                        key_vault.put(key)
+                    }
+                    unclaimed_partner_keys.insert(account_address, key_vault);
+                }
+
                 badge_option = Some(partner_nft_badge_resource.clone());
-                badge_local_id = Some(partner_nft_badge_local.clone());
+                badge_local_ids.push(partner_local_quick_id.clone());
 
                 Runtime::emit_event(PartnerLocked {
                     partner: account_address,
                     partner_badge: partner_nft_badge_resource.clone(),
-                    partner_local_id: partner_nft_badge_local.clone(),
+                    partner_local_id: partner_local_quick_id,
                 });
             }
 
+            // the arbiter's key goes out the same way as the partner keys
+            let mut arbiter_rule = rule!(deny_all);
+            if arbiter.is_some() {
+                let arbiter_account = arbiter.as_ref().unwrap().account;
+                let arbiter_local_id = NonFungibleLocalId::String("Trove_Arbiter_Key".try_into().unwrap());
+                let arbiter_key = a_key.take_non_fungible(&arbiter_local_id);
+                arbiter_rule = rule!(require(NonFungibleGlobalId::new(
+                    arbiter_key.resource_address(),
+                    arbiter_local_id
+                )));
+
+                let arbiter_key_resource = arbiter_key.resource_address();
+                let refused_key = Global::<Account>::from(arbiter_account)
+                    .try_deposit_or_refund(arbiter_key.into(), None);
+                if let Some(refused_key) = refused_key {
+                    let mut key_vault = Vault::new(arbiter_key_resource);
+                    key_vault.put(refused_key);
+                    unclaimed_partner_keys.insert(arbiter_account, key_vault);
+                }
+            }
+
             let global_key_id = NonFungibleGlobalId::new(
                 a_key.resource_address(),
                 a_key.as_non_fungible().non_fungible_local_id(),
@@ -365,28 +856,79 @@ mod barter {
 
             let partner_vaults: HashMap<ResourceAddress, Vault> = HashMap::new();
 
+            let deposit_receipt_manager = ResourceBuilder::new_ruid_non_fungible::<DepositReceipt>(OwnerRole::None)
+                .metadata(metadata! {
+                    roles {
+                        metadata_locker => rule!(deny_all);
+                        metadata_locker_updater => rule!(deny_all);
+                        metadata_setter => rule!(deny_all);
+                        metadata_setter_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => "TROVE Deposit Receipt".to_owned(), locked;
+                        "description" => "Proof of your deposit into a Swap on trove.tools".to_owned(), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                })
+                .mint_roles(mint_roles!(
+                    minter => rule!(require(global_caller(component_address)));
+                    minter_updater => rule!(deny_all);
+                ))
+                .burn_roles(burn_roles!(
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                ))
+                .create_with_no_initial_supply();
+
             Runtime::emit_event(ComponentCreated {
                 component: component_address,
                 creator_badge: a_key.resource_address(),
                 creator_badge_local: a_key.as_non_fungible().non_fungible_local_id(),
             });
 
+            // fees can only be swept out by the registry that set them
+            let fee_collector_rule = match registry {
+                Some(registry_address) => rule!(require(global_caller(registry_address))),
+                None => rule!(deny_all),
+            };
+
             let barter_component = Self {
+                swap_component: component_address,
                 a_vault_key: a_key.resource_address(),
                 a_vault_key_id: a_key.as_non_fungible().non_fungible_local_id(),
                 a_vault_key_global: global_key_id.clone(),
                 creator_vaults: user_a_vaults,
+                nfts_offered: a_nft_deposits,
                 partner_vaults,
                 expected_nfts: expected_b_nft_deposits,
+                expected_collections: b_collection_deposits_unwrap,
                 expected_tokens: expected_b_token_deposits,
-                tokens_validated: false,
-                nfts_validated: false,
                 proposal_resource_manager: a_key.resource_manager(),
+                deposit_receipt_manager,
+                partner_deposit: None,
+                counter_offers: KeyValueStore::new(),
+                counter_offer_ids: Vec::new(),
                 // partner_badge_manager: b_badge_manager,
-                private: private_bool,
                 badge_partner: badge_option,
-                badge_partner_local: badge_local_id,
-                swapped: false,
+                badge_partner_locals: badge_local_ids,
+                filled_by: None,
+                unclaimed_partner_keys,
+                holder_gate,
+                require_approval,
+                pending_withdrawal: None,
+                arbiter,
+                settlement_delay,
+                funded_at: None,
+                disputed: false,
+                status: if draft { SwapStatus::Draft } else { SwapStatus::Open },
+                valid_from,
+                expires_at,
+                registry,
+                fee_config,
+                fee_vaults: HashMap::new(),
+                creator_referral: referral,
+                partner_referral: None,
+                created_at: Clock::current_time(TimePrecision::Minute),
                 category,
             }
             .instantiate()
@@ -407,6 +949,9 @@ mod barter {
             ))
             .roles(roles!(
                 admin => rule!(require(global_key_id));
+                ring => rule!(require(package_of_direct_caller(Runtime::package_address())));
+                fee_collector => fee_collector_rule;
+                arbiter => arbiter_rule;
             ))
             .with_address(address_reservation)
             .globalize();
@@ -414,176 +959,164 @@ mod barter {
             (barter_component, a_key)
         }
 
-        // getter used for testing
+        // creator key, partner key resource and the partner keys' local ids
 
         pub fn get_badge(
-            &mut self,
+            &self,
         ) -> (
             ResourceAddress,
             NonFungibleLocalId,
             NonFungibleGlobalId,
             Option<ResourceAddress>,
-            Option<NonFungibleLocalId>,
+            Vec<NonFungibleLocalId>,
         ) {
             (
                 self.a_vault_key,
                 self.a_vault_key_id.clone(),
                 self.a_vault_key_global.clone(),
                 self.badge_partner.clone(),
-                self.badge_partner_local.clone(),
+                self.badge_partner_locals.clone(),
             )
         }
 
-        pub fn partner_deposit_nfts(&mut self, b_nft_assets: Vec<Bucket>, b_badge: Option<Proof>) {
-            assert!(!self.swapped, "Swap has occurred already occured");
-            // if swap required badge
-            if self.private {
-                // check badge has been passed
-                assert!(b_badge.is_some(), "Badge required");
+        /// For partners whose account refused the airdropped key. Has to be signed by the named account's owner keys,
+        /// or come with its owner badge if it's been securified. Accounts under any other owner rule can't prove
+        /// ownership here - the creator can take the key back with `creator_reclaim_partner_key` and hand it over instead.
 
-                // check passes badge is valid
-                if b_badge.is_some() {
-                    let badge_to_validate = b_badge.unwrap();
-                    let badge_ref = badge_to_validate.clone();
+        pub fn partner_claim_key(&mut self, account: ComponentAddress) -> Bucket {
+            Runtime::assert_access_rule(account_owner_rule(account));
 
-                    let validation_resource = self.badge_partner.unwrap();
+            let mut key_vault = self
+                .unclaimed_partner_keys
+                .get_mut(&account)
+                .expect("No partner key held for this account");
+            key_vault.take_all()
+        }
 
-                    let badge_local_to_validate = badge_ref.check(validation_resource);
+        /// For a partner key that's stuck because its account can't prove ownership to `partner_claim_key`. The creator
+        /// gets it back to pass on themselves, e.g. in a transaction the partner signs too. Arbiter keys stay put.
 
-                    let badge_proof_local = badge_local_to_validate
-                        .as_non_fungible()
-                        .non_fungible_local_id();
-                    let validation_local = self.badge_partner_local.as_ref().unwrap();
-                    assert!(&badge_proof_local == validation_local, "Incorrect badge")
-                }
-            }
+        pub fn creator_reclaim_partner_key(&mut self, account: ComponentAddress) -> Bucket {
+            let mut key_vault = self
+                .unclaimed_partner_keys
+                .get_mut(&account)
+                .expect("No partner key held for this account");
+            let arbiter_local_id = NonFungibleLocalId::String("Trove_Arbiter_Key".try_into().unwrap());
+            assert!(
+                !key_vault.as_non_fungible().contains_non_fungible(&arbiter_local_id),
+                "Arbiter keys can't be reclaimed"
+            );
+            key_vault.take_all()
+        }
 
-            let mut nft_record: Vec<NonFungibleGlobalId> = Vec::new();
+        // deposit receipt resource and the local id of the receipt currently holding the partner deposit
 
-            for i in &b_nft_assets {
-                let nft_resource = i.resource_address();
+        pub fn get_deposit_receipt(&self) -> (ResourceAddress, Option<NonFungibleLocalId>) {
+            (
+                self.deposit_receipt_manager.address(),
+                self.partner_deposit.as_ref().map(|deposit| deposit.receipt.clone()),
+            )
+        }
 
-                if i.as_non_fungible().non_fungible_local_ids().len() > 1 {
-                    let fucket = i.as_non_fungible().non_fungible_local_ids();
-                    for nft_id in fucket.into_iter() {
-                        let global_ar: NonFungibleGlobalId =
-                            NonFungibleGlobalId::new(nft_resource, nft_id);
-                        nft_record.push(global_ar)
-                    }
-                } else {
-                    let nft_id = i.as_non_fungible().non_fungible_local_id();
-                    let nft_global = NonFungibleGlobalId::new(nft_resource, nft_id);
-                    nft_record.push(nft_global)
-                }
-            }
+        /// The first deposit mints a receipt which is returned to the depositor. Any follow-up deposit (e.g. tokens after NFTs)
+        /// has to present that receipt, so the partner side of a swap can only ever be funded by one party.
+        /// Each side has to actually be requested, and actually be deposited - otherwise anyone could bind the swap for free.
+        /// Each call is capped at 50 NFTs or 50 token buckets, so a bigger request can be filled over several calls under the same receipt.
 
+        pub fn partner_deposit_nfts(
+            &mut self,
+            b_nft_assets: Vec<Bucket>,
+            b_badge: Option<Proof>,
+            deposit_receipt: Option<Proof>,
+            // only counts on the first deposit - whoever brought the depositor in
+            referral: Option<Referral>,
+        ) -> Option<Bucket> {
+            self.check_trading_window();
+            let partner_key = self.check_partner_badge(b_badge);
             assert!(
-                self.expected_nfts
-                    .iter()
-                    .all(|item| nft_record.contains(item)),
-                "no match: {:?} vs {:?}",
-                nft_record,
-                self.expected_nfts
+                !self.expected_nfts.is_empty() || !self.expected_collections.is_empty(),
+                "Swap doesn't request any NFTs"
             );
-
-            for bucket in b_nft_assets.into_iter() {
-                self.partner_vaults
-                    .entry(bucket.resource_address())
-                    .or_insert_with(|| Vault::new(bucket.resource_address()))
-                    .put(bucket)
+            let mut nft_record = Self::nft_ids(&b_nft_assets);
+            assert!(!nft_record.is_empty(), "Nothing deposited");
+            assert!(nft_record.len() <= 50, "Reached single transaction event limit");
+
+            let receipt = self.bind_depositor(deposit_receipt, partner_key);
+            if receipt.is_some() {
+                Self::check_referral(&referral);
+                self.partner_referral = referral;
             }
-
-            self.nfts_validated = true
+            let deposit = self.partner_deposit.as_ref().unwrap();
+            assert!(!deposit.nfts, "NFTs have already been deposited");
+            nft_record.extend(deposit.nfts_received.iter().cloned());
+            let complete = self.validate_nft_deposit(&nft_record);
+
+            let deposit = self.partner_deposit.as_mut().unwrap();
+            deposit.nfts = complete;
+            deposit.nfts_received = nft_record;
+            self.store_partner_assets(b_nft_assets);
+            self.deposit_received();
+
+            receipt
         }
 
         pub fn partner_deposit_tokens(
             &mut self,
             b_token_assets: Vec<Bucket>,
             b_badge: Option<Proof>,
-        ) {
-            assert!(!self.swapped, "Swap has occurred already occured");
-            // if swap required badge
-            if self.private {
-                // check badge has been passed
-                assert!(b_badge.is_some(), "Badge required");
-
-                // check passes badge is valid
-                if b_badge.is_some() {
-                    let badge_to_validate = b_badge.unwrap();
-                    let badge_ref = badge_to_validate.clone();
-
-                    let validation_resource = self.badge_partner.unwrap();
-
-                    let badge_local_to_validate = badge_ref.check(validation_resource);
+            deposit_receipt: Option<Proof>,
+            // only counts on the first deposit - whoever brought the depositor in
+            referral: Option<Referral>,
+        ) -> Option<Bucket> {
+            self.check_trading_window();
+            let partner_key = self.check_partner_badge(b_badge);
+            assert!(!self.expected_tokens.is_empty(), "Swap doesn't request any tokens");
+            assert!(!b_token_assets.is_empty(), "Nothing deposited");
+            assert!(b_token_assets.len() <= 50, "Reached single transaction event limit");
+            assert!(
+                b_token_assets.iter().all(|bucket| !bucket.is_empty()),
+                "Nothing deposited"
+            );
 
-                    let badge_proof_local = badge_local_to_validate
-                        .as_non_fungible()
-                        .non_fungible_local_id();
-                    let validation_local = self.badge_partner_local.as_ref().unwrap();
-                    assert!(&badge_proof_local == validation_local, "Incorrect badge")
-                }
+            let receipt = self.bind_depositor(deposit_receipt, partner_key);
+            if receipt.is_some() {
+                Self::check_referral(&referral);
+                self.partner_referral = referral;
             }
-
-            let token_criteria = self.expected_tokens.clone();
-            let mut b_deposit_hm: HashMap<ResourceAddress, Decimal> = HashMap::new();
-            for bucket in b_token_assets.iter() {
-                b_deposit_hm.insert(bucket.resource_address(), bucket.amount());
+            let deposit = self.partner_deposit.as_ref().unwrap();
+            assert!(!deposit.tokens, "Tokens have already been deposited");
+            let mut received = deposit.tokens_received.clone();
+            for (resource_address, amount) in Self::token_amounts(&b_token_assets).into_iter() {
+                *received.entry(resource_address).or_insert(Decimal::zero()) += amount;
             }
+            let complete = self.validate_token_deposit(&received);
 
-            assert!(b_deposit_hm == token_criteria, "Token deposits don't match");
-
-            for bucket in b_token_assets.into_iter() {
-                self.partner_vaults
-                    .entry(bucket.resource_address())
-                    .or_insert_with(|| Vault::new(bucket.resource_address()))
-                    .put(bucket)
-            }
+            let deposit = self.partner_deposit.as_mut().unwrap();
+            deposit.tokens = complete;
+            deposit.tokens_received = received;
+            self.store_partner_assets(b_token_assets);
+            self.deposit_received();
 
-            self.tokens_validated = true
+            receipt
         }
 
-        /// Of note - There is a possibility that someone could snipe the offered assets if the partner was to just
-        /// manually send the right assets and not claim the new assets all in the same transaction. Couldn't happen if the swap
-        /// is set to private. However, if we're talking atomic transactions and you do it right, there's no issue.
-        /// Only an issue if people try to fool around with dev console submitting things wrong, but then the risk is on then imo XD
-
-        pub fn partner_claims_creator_assets(&mut self, b_badge: Option<Proof>) -> Vec<Bucket> {
-            assert!(!self.swapped, "Swap has occurred already occured");
-            // if swap required badge
-            if self.private {
-                // check badge has been passed
-                assert!(b_badge.is_some(), "Badge required");
-
-                // check passes badge is valid
-                if b_badge.is_some() {
-                    let badge_to_validate = b_badge.unwrap();
-                    let badge_ref = badge_to_validate.clone();
+        /// Only the holder of the deposit receipt can claim, so a funded swap can't be sniped by someone else
+        /// even if the deposit and the claim are split across transactions.
 
-                    let validation_resource = self.badge_partner.unwrap();
-
-                    let badge_local_to_validate = badge_ref.check(validation_resource);
-
-                    let badge_proof_local = badge_local_to_validate
-                        .as_non_fungible()
-                        .non_fungible_local_id();
-                    let validation_local = self.badge_partner_local.as_ref().unwrap();
-                    assert!(&badge_proof_local == validation_local, "Incorrect badge")
-                }
+        pub fn partner_claims_creator_assets(
+            &mut self,
+            b_badge: Option<Proof>,
+            deposit_receipt: Proof,
+            // Optional proof of the registry's membership resource, for a discount on the platform fee
+            membership: Option<Proof>,
+        ) -> Vec<Bucket> {
+            // the arbiter may have settled the swap already
+            if self.status != SwapStatus::Settled {
+                self.settle_partner_claim(b_badge, deposit_receipt);
+            } else {
+                self.check_deposit_receipt(deposit_receipt);
             }
 
-            if self.expected_nfts.len() > 0 {
-                assert!(
-                    self.nfts_validated,
-                    "Insufficient assets deposited for trade"
-                )
-            };
-            if self.expected_tokens.len() > 0 {
-                assert!(
-                    self.tokens_validated,
-                    "Insufficient assets deposited for trade"
-                )
-            };
-
             let a_assets: Vec<ResourceAddress> = self.creator_vaults.keys().cloned().collect();
 
             let mut buckets: Vec<Bucket> = Vec::new();
@@ -596,73 +1129,487 @@ mod barter {
                         .take_all(),
                 )
             }
-            self.swapped = true;
 
-            return buckets;
+            let referral = self.partner_referral.clone();
+            return self.charge_fees(buckets, referral, membership);
         }
 
-        pub fn partner_cancel(&mut self, b_badge: Option<Proof>) -> Vec<Bucket> {
-            assert!(!self.swapped, "Swap has occurred already occured");
-            if self.private {
-                // check badge has been passed
-                assert!(b_badge.is_some(), "Badge required");
-
-                // check passes badge is valid
-                if b_badge.is_some() {
-                    let badge_to_validate = b_badge.unwrap();
-                    let badge_ref = badge_to_validate.clone();
+        /// Same as `partner_claims_creator_assets` but only hands back up to `limit` assets per call (each NFT counts as one,
+        /// each fungible resource as one), for bundles too big to withdraw in one transaction. The first call settles the
+        /// swap - keep calling with the same receipt until it comes back empty.
 
-                    let validation_resource = self.badge_partner.unwrap();
-
-                    let badge_local_to_validate = badge_ref.check(validation_resource);
-
-                    let badge_proof_local = badge_local_to_validate
-                        .as_non_fungible()
-                        .non_fungible_local_id();
-                    let validation_local = self.badge_partner_local.as_ref().unwrap();
-                    assert!(&badge_proof_local == validation_local, "Incorrect badge")
-                }
+        pub fn partner_claim_page(
+            &mut self,
+            b_badge: Option<Proof>,
+            deposit_receipt: Proof,
+            limit: u32,
+            membership: Option<Proof>,
+        ) -> Vec<Bucket> {
+            if self.status != SwapStatus::Settled {
+                self.settle_partner_claim(b_badge, deposit_receipt);
+            } else {
+                self.check_deposit_receipt(deposit_receipt);
             }
 
-            let b_assets: Vec<ResourceAddress> = self.partner_vaults.keys().cloned().collect();
+            let buckets = Self::take_page(&mut self.creator_vaults, limit);
+            let referral = self.partner_referral.clone();
+            self.charge_fees(buckets, referral, membership)
+        }
+
+        /// Deposits the partner's side and hands back the creator's assets in a single call, so a public swap
+        /// can't be sniped between the deposit and the claim - even from a hand-written manifest.
+        /// Both sides are validated against `expected_nfts`/`expected_tokens` before anything moves.
+
+        pub fn accept_trade(
+            &mut self,
+            b_nft_assets: Vec<Bucket>,
+            b_token_assets: Vec<Bucket>,
+            b_badge: Option<Proof>,
+            referral: Option<Referral>,
+            membership: Option<Proof>,
+        ) -> Vec<Bucket> {
+            self.check_trading_window();
+            assert!(self.status == SwapStatus::Open, "A partner deposit is already pending");
+            assert!(!self.require_approval, "Swap needs the creator's approval - deposit instead");
+            assert!(self.arbiter.is_none(), "Swap has an arbiter - deposit instead");
+            assert!(self.settlement_delay.is_none(), "Swap has a settlement delay - deposit instead");
+            let partner_key = self.check_partner_badge(b_badge);
+            Self::check_referral(&referral);
+            self.check_no_pending_withdrawal();
+
+            assert!(
+                self.validate_nft_deposit(&Self::nft_ids(&b_nft_assets)),
+                "NFT deposits don't match"
+            );
+            assert!(
+                self.validate_token_deposit(&Self::token_amounts(&b_token_assets)),
+                "Token deposits don't match"
+            );
+
+            self.store_partner_assets(b_nft_assets);
+            self.store_partner_assets(b_token_assets);
+            self.pay_settlement_royalties();
+
+            let a_assets: Vec<ResourceAddress> = self.creator_vaults.keys().cloned().collect();
 
             let mut buckets: Vec<Bucket> = Vec::new();
 
-            for resource_address in b_assets.into_iter() {
+            for resource_address in a_assets.into_iter() {
                 buckets.push(
-                    self.partner_vaults
+                    self.creator_vaults
                         .get_mut(&resource_address)
                         .unwrap()
                         .take_all(),
                 )
             }
+            self.set_status(SwapStatus::Settled);
+            self.filled_by = partner_key;
+            self.partner_referral = referral;
 
-            return buckets;
+            let referral = self.partner_referral.clone();
+            return self.charge_fees(buckets, referral, membership);
+        }
+
+        /// Still works once the swap's been cancelled or has expired, so a deposit can never get stuck.
+
+        pub fn partner_cancel(&mut self, b_badge: Option<Proof>, deposit_receipt: Proof) -> Vec<Bucket> {
+            self.partner_cancel_page(b_badge, deposit_receipt, u32::MAX)
+        }
+
+        /// Same as `partner_cancel` but hands back at most `limit` assets per call, for deposits too big to pull out
+        /// in one go. The first call frees the swap up - keep calling with the receipt until nothing comes back.
+
+        pub fn partner_cancel_page(&mut self, b_badge: Option<Proof>, deposit_receipt: Proof, limit: u32) -> Vec<Bucket> {
+            let receipt_id = self.receipt_id(deposit_receipt);
+
+            if self.partner_deposit.as_ref().map(|deposit| &deposit.receipt) == Some(&receipt_id) {
+                assert!(self.status != SwapStatus::Settled, "Swap has occurred already occured");
+                assert!(
+                    self.status == SwapStatus::Cancelled || !self.is_locked(),
+                    "Swap is locked until its dispute window and settlement delay have passed"
+                );
+                self.check_partner_badge(b_badge);
+
+                // free the partner side up for someone else
+                self.release_deposit();
+            }
+
+            self.take_withdrawal_page(&receipt_id, limit)
         }
 
+        /// Also how the creator gets their assets back out of an expired swap. Any partner deposit is left for the
+        /// depositor to pull back out with `partner_cancel`.
+
         pub fn creator_cancel(&mut self) -> Vec<Bucket> {
-            assert!(!self.swapped, "Swap has occurred");
+            self.creator_cancel_page(u32::MAX)
+        }
 
-            // update state such that swap has occurred - ie. block deposits.
-            self.swapped = true;
-            let a_assets: Vec<ResourceAddress> = self.creator_vaults.keys().cloned().collect();
+        /// Same as `creator_cancel` but hands back at most `limit` assets per call - keep calling until nothing comes back.
+
+        pub fn creator_cancel_page(&mut self, limit: u32) -> Vec<Bucket> {
+            assert!(self.status != SwapStatus::Settled, "Swap has occurred");
+            assert!(
+                self.status == SwapStatus::Cancelled || !self.is_locked(),
+                "Swap is locked until its dispute window and settlement delay have passed"
+            );
+
+            // update state such that the swap is closed - ie. block deposits.
+            // An arbiter refund has already closed it.
+            if self.status != SwapStatus::Expired && self.status != SwapStatus::Cancelled {
+                self.set_status(SwapStatus::Cancelled);
+            }
+
+            Self::take_page(&mut self.creator_vaults, limit)
+        }
+
+        /// Permissionless so a frontend (or anyone) can tidy up stale offers. Once expired, no partner-side method
+        /// will go through and the creator can pull their assets back out with `creator_cancel`.
+        /// A funded swap can't be expired out from under its lock, or out from under an arbiter deciding a dispute.
+
+        pub fn expire(&mut self) {
+            assert!(
+                matches!(
+                    self.status,
+                    SwapStatus::Draft
+                        | SwapStatus::Open
+                        | SwapStatus::PartnerFunded
+                        | SwapStatus::PendingApproval
+                        | SwapStatus::Approved
+                ),
+                "Swap is already closed"
+            );
+            assert!(self.expires_at.is_some(), "Swap has no expiry");
+            assert!(!self.is_locked(), "Swap is locked until its dispute window and settlement delay have passed");
+            assert!(!(self.disputed && self.arbiter.is_some()), "Swap is waiting on the arbiter");
+            assert!(
+                Clock::current_time_is_at_or_after(self.expires_at.unwrap(), TimePrecision::Minute),
+                "Swap hasn't expired yet"
+            );
+
+            self.set_status(SwapStatus::Expired);
+
+            Runtime::emit_event(ProposalExpired {
+                component: self.swap_component,
+            });
+        }
+
+        /// Lets a taker put forward a different bundle to what was requested. It's escrowed against a receipt
+        /// until the creator accepts or rejects it - and the taker can pull it back out with that receipt any time before.
+
+        pub fn propose_counter_offer(
+            &mut self,
+            b_nft_assets: Vec<Bucket>,
+            b_token_assets: Vec<Bucket>,
+            b_badge: Option<Proof>,
+            referral: Option<Referral>,
+        ) -> Bucket {
+            self.check_trading_window();
+            let partner_key = self.check_partner_badge(b_badge);
+            Self::check_referral(&referral);
+            assert!(
+                !b_nft_assets.is_empty() || !b_token_assets.is_empty(),
+                "You need to offer something"
+            );
+
+            let mut nfts_offered: Vec<NonFungibleGlobalId> = Vec::new();
+            for bucket in b_nft_assets.iter() {
+                let resource = bucket.resource_address();
+                for nft_id in bucket.as_non_fungible().non_fungible_local_ids().into_iter() {
+                    nfts_offered.push(NonFungibleGlobalId::new(resource, nft_id))
+                }
+            }
+
+            let mut tokens_offered: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            for bucket in b_token_assets.iter() {
+                *tokens_offered
+                    .entry(bucket.resource_address())
+                    .or_insert(Decimal::zero()) += bucket.amount();
+            }
+
+            let mut vaults: HashMap<ResourceAddress, Vault> = HashMap::new();
+            for bucket in b_nft_assets.into_iter().chain(b_token_assets.into_iter()) {
+                vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+
+            let receipt = self.mint_deposit_receipt();
+            let counter_id = receipt.as_non_fungible().non_fungible_local_id();
+
+            Runtime::emit_event(CounterOfferProposed {
+                component: self.swap_component,
+                counter_id: counter_id.clone(),
+                nfts_offered: nfts_offered.clone(),
+                tokens_offered: tokens_offered.clone(),
+            });
+
+            self.counter_offers.insert(
+                counter_id.clone(),
+                CounterOffer {
+                    vaults,
+                    nfts_offered,
+                    tokens_offered,
+                    partner_key,
+                    referral,
+                    status: CounterStatus::Pending,
+                },
+            );
+            self.counter_offer_ids.push(counter_id);
+
+            receipt
+        }
+
+        /// Creator takes the counter offer's bundle, and their own assets are left for the taker to collect.
+        /// Can't be done while a regular partner deposit is sitting in the swap. Settles on the spot like
+        /// `accept_trade`, so it's off for swaps with an arbiter or a settlement delay too.
+
+        pub fn creator_accept_counter(&mut self, counter_id: NonFungibleLocalId) -> Vec<Bucket> {
+            assert!(self.status != SwapStatus::PartnerFunded, "A partner deposit is already pending");
+            assert!(self.status == SwapStatus::Open, "Swap is closed");
+            assert!(self.arbiter.is_none(), "Swap has an arbiter - counter offers can't be accepted");
+            assert!(
+                self.settlement_delay.is_none(),
+                "Swap has a settlement delay - counter offers can't be accepted"
+            );
+            // a deposit still waiting to be withdrawn would get swept up in the creator's claim
+            self.check_no_pending_withdrawal();
+
+            let mut counter = self
+                .counter_offers
+                .get_mut(&counter_id)
+                .expect("No such counter offer");
+            assert!(
+                counter.status == CounterStatus::Pending,
+                "Counter offer is no longer pending"
+            );
+
+            // same royalties as a regular settlement, with the counter offer standing in for the partner side
+            let creator_sold = Self::count_nfts(&self.nfts_offered);
+            let counter_sold = Self::vault_nft_counts(&counter.vaults);
+            Self::pay_royalties(self.swap_component, &mut counter.vaults, &creator_sold);
+            Self::pay_royalties(self.swap_component, &mut self.creator_vaults, &counter_sold);
 
             let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in counter.vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+            counter.status = CounterStatus::Accepted;
+            let partner_key = counter.partner_key.clone();
+            let partner_referral = counter.referral.clone();
+            drop(counter);
+
+            self.set_status(SwapStatus::Settled);
+            self.filled_by = partner_key;
+            self.partner_referral = partner_referral;
+
+            Runtime::emit_event(CounterOfferDecided {
+                component: self.swap_component,
+                counter_id,
+                accepted: true,
+            });
 
-            for resource_address in a_assets.into_iter() {
-                buckets.push(
-                    self.creator_vaults
-                        .get_mut(&resource_address)
-                        .unwrap()
-                        .take_all(),
-                )
+            let referral = self.creator_referral.clone();
+            return self.charge_fees(buckets, referral, None);
+        }
+
+        pub fn creator_reject_counter(&mut self, counter_id: NonFungibleLocalId) {
+            let mut counter = self
+                .counter_offers
+                .get_mut(&counter_id)
+                .expect("No such counter offer");
+            assert!(
+                counter.status == CounterStatus::Pending,
+                "Counter offer is no longer pending"
+            );
+            counter.status = CounterStatus::Rejected;
+            drop(counter);
+
+            Runtime::emit_event(CounterOfferDecided {
+                component: self.swap_component,
+                counter_id,
+                accepted: false,
+            });
+        }
+
+        /// Accepted counter offers collect the creator's assets, anything else gets the taker's own bundle back.
+
+        pub fn partner_claim_counter(&mut self, counter_receipt: Proof) -> Vec<Bucket> {
+            let counter_id = counter_receipt
+                .check(self.deposit_receipt_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            let mut counter = self
+                .counter_offers
+                .get_mut(&counter_id)
+                .expect("No such counter offer");
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+
+            let status = counter.status.clone();
+            match status {
+                CounterStatus::Accepted => {
+                    counter.status = CounterStatus::Claimed;
+                    drop(counter);
+
+                    let a_assets: Vec<ResourceAddress> =
+                        self.creator_vaults.keys().cloned().collect();
+                    for resource_address in a_assets.into_iter() {
+                        buckets.push(
+                            self.creator_vaults
+                                .get_mut(&resource_address)
+                                .unwrap()
+                                .take_all(),
+                        )
+                    }
+                    let referral = self.partner_referral.clone();
+                    buckets = self.charge_fees(buckets, referral, None);
+                }
+                CounterStatus::Pending | CounterStatus::Rejected => {
+                    for vault in counter.vaults.values_mut() {
+                        buckets.push(vault.take_all())
+                    }
+                    counter.status = CounterStatus::Withdrawn;
+                }
+                _ => panic!("Counter offer has already been collected"),
             }
-            self.swapped = true;
+
             return buckets;
         }
 
-        pub fn creator_claims_partner_assets(&mut self) -> Vec<Bucket> {
-            assert!(self.swapped, "Swap hasn't occurred yet");
+        pub fn get_counter_offers(&self) -> Vec<NonFungibleLocalId> {
+            self.counter_offer_ids.clone()
+        }
+
+        pub fn status(&self) -> SwapStatus {
+            self.status.clone()
+        }
+
+        pub fn get_proposal(&self) -> ProposalView {
+            let balances = |vaults: &HashMap<ResourceAddress, Vault>| -> HashMap<ResourceAddress, Decimal> {
+                vaults
+                    .iter()
+                    .map(|(resource_address, vault)| (*resource_address, vault.amount()))
+                    .collect()
+            };
+
+            ProposalView {
+                component: self.swap_component,
+                status: self.status.clone(),
+                created_at: self.created_at,
+                valid_from: self.valid_from,
+                expires_at: self.expires_at,
+                require_approval: self.require_approval,
+                arbiter: self.arbiter.as_ref().map(|arbitration| arbitration.account),
+                settlement_delay: self.settlement_delay,
+                funded_at: self.funded_at,
+                disputed: self.disputed,
+                creator_badge: self.a_vault_key_global.clone(),
+                nfts_offered: self.nfts_offered.clone(),
+                offered_balances: balances(&self.creator_vaults),
+                nfts_requested: self.expected_nfts.clone(),
+                collections_requested: self.expected_collections.clone(),
+                tokens_requested: self.expected_tokens.clone(),
+                partner_badge: self.badge_partner,
+                partner_keys: self.badge_partner_locals.clone(),
+                holder_gate: self.holder_gate.clone(),
+                filled_by: self.filled_by.clone(),
+                partner_deposit: self.partner_deposit.clone(),
+                partner_balances: balances(&self.partner_vaults),
+            }
+        }
+
+        /// Adds fungibles to a draft's offer. Capped at 50 per call - just call it again for more.
+
+        pub fn add_offer_tokens(&mut self, a_tokens: Vec<Bucket>) {
+            assert!(self.status == SwapStatus::Draft, "Swap has already been published");
+            assert!(a_tokens.len() <= 50, "Reached single transaction event limit");
+
+            for bucket in a_tokens.into_iter() {
+                if self.category.is_none() {
+                    self.category = Some(bucket.resource_address());
+                }
+                self.creator_vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+        }
+
+        pub fn add_offer_nfts(&mut self, a_nfts: Vec<Bucket>) {
+            assert!(self.status == SwapStatus::Draft, "Swap has already been published");
+            let mut a_nft_len = 0;
+            for bucket in a_nfts.iter() {
+                a_nft_len = a_nft_len + bucket.as_non_fungible().non_fungible_local_ids().len();
+            }
+            assert!(a_nft_len <= 50, "Reached single transaction event limit");
+
+            for bucket in a_nfts.into_iter() {
+                if self.category.is_none() {
+                    self.category = Some(bucket.resource_address());
+                }
+                let resource = bucket.resource_address();
+                for nft_id in bucket.as_non_fungible().non_fungible_local_ids().into_iter() {
+                    self.nfts_offered.push(NonFungibleGlobalId::new(resource, nft_id))
+                }
+                self.creator_vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+        }
+
+        pub fn add_request_nfts(&mut self, b_nft_deposits: Vec<NonFungibleGlobalId>) {
+            assert!(self.status == SwapStatus::Draft, "Swap has already been published");
+            assert!(b_nft_deposits.len() <= 50, "Reached single transaction event limit");
+
+            for nft in b_nft_deposits.into_iter() {
+                assert!(!self.expected_nfts.contains(&nft), "NFT already requested");
+                self.expected_nfts.push(nft)
+            }
+        }
+
+        pub fn add_request_collections(&mut self, b_collection_deposits: Vec<CollectionRequest>) {
+            assert!(self.status == SwapStatus::Draft, "Swap has already been published");
+            let b_collection_len = Self::collection_count(&b_collection_deposits);
+            assert!(b_collection_len <= 50, "Reached single transaction event limit");
+
+            self.expected_collections.extend(b_collection_deposits);
+        }
+
+        // requesting more of a token that's already been asked for adds to the amount
+        pub fn add_request_tokens(&mut self, b_token_deposits: HashMap<ResourceAddress, Decimal>) {
+            assert!(self.status == SwapStatus::Draft, "Swap has already been published");
+            assert!(b_token_deposits.len() <= 50, "Reached single transaction event limit");
+
+            for (resource_address, amount) in b_token_deposits.into_iter() {
+                *self
+                    .expected_tokens
+                    .entry(resource_address)
+                    .or_insert(Decimal::zero()) += amount;
+            }
+        }
+
+        /// Opens a draft up to partners. Nothing can be added after this.
+
+        pub fn publish(&mut self) {
+            assert!(self.status == SwapStatus::Draft, "Swap has already been published");
+            if (self.expected_nfts.is_empty() && self.expected_collections.is_empty() && self.expected_tokens.is_empty())
+                || self.creator_vaults.values().all(|vault| vault.is_empty())
+            {
+                panic!("You need to offer/request something")
+            }
+
+            self.set_status(SwapStatus::Open);
+
+            Runtime::emit_event(ProposalPublished {
+                component: self.swap_component,
+            });
+        }
+
+        pub fn creator_claims_partner_assets(&mut self, membership: Option<Proof>) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Settled, "Swap hasn't occurred yet");
 
             let b_assets: Vec<ResourceAddress> = self.partner_vaults.keys().cloned().collect();
 
@@ -677,9 +1624,180 @@ mod barter {
                 )
             }
 
+            let referral = self.creator_referral.clone();
+            return self.charge_fees(buckets, referral, membership);
+        }
+
+        /// First half of a ring trade - hands the creator's side to the RingSettlement so it can be routed to the next
+        /// swap in the ring. Only public swaps that nobody has started funding can be part of a ring.
+
+        pub fn ring_release(&mut self) -> Vec<Bucket> {
+            self.check_trading_window();
+            assert!(self.status == SwapStatus::Open, "A partner deposit is already pending");
+            assert!(
+                self.badge_partner_locals.is_empty() && self.holder_gate.is_none(),
+                "Only public swaps can be part of a ring"
+            );
+            assert!(
+                !self.require_approval && self.arbiter.is_none() && self.settlement_delay.is_none(),
+                "Swaps needing approval, an arbiter or a settlement delay can't be part of a ring"
+            );
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.creator_vaults.values_mut() {
+                if !vault.is_empty() {
+                    buckets.push(vault.take_all())
+                }
+            }
+
+            return buckets;
+        }
+
+        /// Second half - the previous swap's creator assets come in as this swap's partner side, checked against the
+        /// request like any other deposit, and the swap settles. The creator collects as usual with `creator_claims_partner_assets`.
+
+        pub fn ring_fill(&mut self, b_assets: Vec<Bucket>) {
+            assert!(self.status == SwapStatus::Open, "Swap is closed");
+            assert!(
+                self.creator_vaults.values().all(|vault| vault.is_empty()),
+                "Creator's side hasn't been released"
+            );
+            self.check_no_pending_withdrawal();
+
+            let (b_token_assets, b_nft_assets): (Vec<Bucket>, Vec<Bucket>) = b_assets
+                .into_iter()
+                .partition(|bucket| bucket.resource_address().is_fungible());
+
+            assert!(
+                self.validate_nft_deposit(&Self::nft_ids(&b_nft_assets)),
+                "NFT deposits don't match"
+            );
+            assert!(
+                self.validate_token_deposit(&Self::token_amounts(&b_token_assets)),
+                "Token deposits don't match"
+            );
+
+            self.store_partner_assets(b_nft_assets);
+            self.store_partner_assets(b_token_assets);
+            // the creator's side has already gone on to the next swap in the ring, which pays royalties on
+            // whatever the previous swap's creator sold - so only this creator's own sales are settled here
+            self.pay_settlement_royalties();
+
+            self.set_status(SwapStatus::Settled);
+        }
+
+        /// Either side can flag a funded swap while it's locked - the creator with their key, the partner with their
+        /// deposit receipt. A disputed swap won't settle once the lock runs out: the arbiter (if there is one) decides,
+        /// otherwise each side can only take their own assets back.
+
+        pub fn raise_dispute(&mut self, proof: Proof) {
+            assert!(
+                matches!(
+                    self.status,
+                    SwapStatus::PartnerFunded | SwapStatus::PendingApproval | SwapStatus::Approved
+                ),
+                "Swap isn't funded"
+            );
+            assert!(self.is_locked(), "Too late to raise a dispute");
+
+            let by_creator = proof.resource_address() == self.a_vault_key;
+            if by_creator {
+                let key_local = proof
+                    .check(self.a_vault_key)
+                    .as_non_fungible()
+                    .non_fungible_local_id();
+                assert!(key_local == self.a_vault_key_id, "Incorrect badge");
+            } else {
+                self.check_deposit_receipt(proof);
+            }
+            self.disputed = true;
+
+            Runtime::emit_event(DisputeRaised {
+                component: self.swap_component,
+                by_creator,
+            });
+        }
+
+        /// Settles the swap during the dispute window - both sides can then collect as normal.
+
+        pub fn arbiter_release(&mut self) {
+            self.check_arbiter_window();
+
+            self.pay_settlement_royalties();
+            self.set_status(SwapStatus::Settled);
+            self.filled_by = self.partner_deposit.as_ref().unwrap().partner_key.clone();
+
+            Runtime::emit_event(ArbiterDecided {
+                component: self.swap_component,
+                released: true,
+            });
+        }
+
+        /// Unwinds the swap during the dispute window - the creator takes their side back with `creator_cancel`,
+        /// the partner with `partner_cancel`.
+
+        pub fn arbiter_refund(&mut self) {
+            self.check_arbiter_window();
+
+            self.set_status(SwapStatus::Cancelled);
+
+            Runtime::emit_event(ArbiterDecided {
+                component: self.swap_component,
+                released: false,
+            });
+        }
+
+        /// For swaps created with `require_approval` - lets the partner who funded the swap go ahead and claim.
+
+        pub fn creator_approve(&mut self) {
+            assert!(self.status == SwapStatus::PendingApproval, "No deposit waiting for approval");
+            self.set_status(SwapStatus::Approved);
+        }
+
+        /// Turns the funded deposit down. The partner collects their assets with `partner_claim_refund`, and the swap
+        /// opens back up for someone else once they have - if they never do, the creator can still cancel.
+
+        pub fn creator_reject(&mut self) {
+            assert!(self.status == SwapStatus::PendingApproval, "No deposit waiting for approval");
+            assert!(
+                !self.is_locked(),
+                "Swap is locked until its dispute window and settlement delay have passed"
+            );
+
+            self.release_deposit();
+        }
+
+        pub fn partner_claim_refund(&mut self, deposit_receipt: Proof) -> Vec<Bucket> {
+            self.partner_claim_refund_page(deposit_receipt, u32::MAX)
+        }
+
+        // paged version of the above, for deposits too big to pull out in one go
+        pub fn partner_claim_refund_page(&mut self, deposit_receipt: Proof, limit: u32) -> Vec<Bucket> {
+            let receipt_id = self.receipt_id(deposit_receipt);
+            self.take_withdrawal_page(&receipt_id, limit)
+        }
+
+        // the registry sweeps collected fees out to its fee receiver
+        pub fn withdraw_fees(&mut self) -> Vec<Bucket> {
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.fee_vaults.values_mut() {
+                if !vault.is_empty() {
+                    buckets.push(vault.take_all())
+                }
+            }
+
             return buckets;
         }
 
+        // paged version of the above, for bundles too big to withdraw in one go
+        pub fn creator_claim_page(&mut self, limit: u32, membership: Option<Proof>) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Settled, "Swap hasn't occurred yet");
+
+            let buckets = Self::take_page(&mut self.partner_vaults, limit);
+            let referral = self.creator_referral.clone();
+            self.charge_fees(buckets, referral, membership)
+        }
+
         // after accepted
         pub fn burn_partner_badge(&mut self, burn_token: Bucket) {
             assert!(
@@ -687,14 +1805,47 @@ mod barter {
                 "invalid key"
             );
             assert!(
-                &burn_token.as_non_fungible().non_fungible_local_id()
-                    == self.badge_partner_local.as_ref().unwrap(),
+                self.badge_partner_locals
+                    .contains(&burn_token.as_non_fungible().non_fungible_local_id()),
                 "invalid key"
             );
             let resource_manager: ResourceManager = self.proposal_resource_manager;
             resource_manager.burn(burn_token);
         }
 
+        // spent receipts can be burnt - but not the one still holding an unclaimed deposit
+        pub fn burn_deposit_receipt(&mut self, burn_token: Bucket) {
+            assert!(
+                burn_token.resource_address() == self.deposit_receipt_manager.address(),
+                "invalid receipt"
+            );
+            let receipt_local = burn_token.as_non_fungible().non_fungible_local_id();
+            // once settled, the bound receipt is what claims the creator's side - e.g. after an arbiter release,
+            // or between pages - so it has to stay until that's all been collected
+            if self.partner_deposit.as_ref().map(|deposit| &deposit.receipt) == Some(&receipt_local) {
+                assert!(
+                    self.status == SwapStatus::Settled,
+                    "Receipt still holds a deposit"
+                );
+                assert!(
+                    self.creator_vaults.values().all(|vault| vault.is_empty()),
+                    "Receipt still has the creator's assets to claim"
+                );
+            }
+            assert!(
+                self.pending_withdrawal.as_ref() != Some(&receipt_local),
+                "Receipt still holds a deposit to withdraw"
+            );
+            if let Some(counter) = self.counter_offers.get(&receipt_local) {
+                assert!(
+                    counter.status == CounterStatus::Claimed
+                        || counter.status == CounterStatus::Withdrawn,
+                    "Receipt still holds a counter offer"
+                );
+            }
+            self.deposit_receipt_manager.burn(burn_token);
+        }
+
         pub fn burn_creator_badge(&mut self, burn_token: Bucket) {
             assert!(
                 burn_token.as_non_fungible().non_fungible_local_id() == self.a_vault_key_id,
@@ -710,5 +1861,639 @@ mod barter {
 
           
         }
+
+        // every status change is an event, and is passed on to the registry so its indexes stay current
+        fn set_status(&mut self, status: SwapStatus) {
+            Runtime::emit_event(StatusChanged {
+                component: self.swap_component,
+                from: self.status.clone(),
+                to: status.clone(),
+            });
+            self.status = status;
+
+            if self.registry.is_some() {
+                Global::<TroveRegistry>::from(self.registry.unwrap()).record_status(self.get_proposal());
+            }
+        }
+
+        // partner side can only act on a live swap, between valid_from and expires_at (if they're set)
+        fn check_trading_window(&self) {
+            self.check_live_status();
+            if self.valid_from.is_some() {
+                assert!(
+                    Clock::current_time_is_at_or_after(self.valid_from.unwrap(), TimePrecision::Minute),
+                    "Swap isn't open yet"
+                );
+            }
+            if self.expires_at.is_some() {
+                assert!(
+                    Clock::current_time_is_strictly_before(self.expires_at.unwrap(), TimePrecision::Minute),
+                    "Swap has expired"
+                );
+            }
+        }
+
+        fn check_live_status(&self) {
+            match self.status {
+                SwapStatus::Open
+                | SwapStatus::PartnerFunded
+                | SwapStatus::PendingApproval
+                | SwapStatus::Approved => {}
+                SwapStatus::Draft => panic!("Swap hasn't been published yet"),
+                SwapStatus::Expired => panic!("Swap has expired"),
+                _ => panic!("Swap has occurred already occured"),
+            }
+        }
+
+        // everything that has to hold before the partner can take the creator's side - marks the swap as done
+        fn settle_partner_claim(&mut self, b_badge: Option<Proof>, deposit_receipt: Proof) {
+            // a lock running past the expiry doesn't stop a swap that was fully funded in time from being claimed
+            if self.funded_at.is_some() {
+                self.check_live_status();
+            } else {
+                self.check_trading_window();
+            }
+            let partner_key = self.check_partner_badge(b_badge);
+            self.check_deposit_receipt(deposit_receipt);
+
+            assert!(self.deposit_complete(), "Insufficient assets deposited for trade");
+            if self.require_approval {
+                assert!(self.status == SwapStatus::Approved, "Creator hasn't approved the deposit");
+            }
+            assert!(!self.is_locked(), "Swap is locked until its dispute window and settlement delay have passed");
+            assert!(!self.disputed, "Swap is disputed");
+
+            self.pay_settlement_royalties();
+            self.set_status(SwapStatus::Settled);
+            self.filled_by = partner_key;
+        }
+
+        // Each side's NFTs earn their collections a royalty out of the fungibles the other side is paying for them.
+        // Has to happen while both sides are still in the vaults, so nobody's claim can get stuck on it afterwards.
+        fn pay_settlement_royalties(&mut self) {
+            let creator_sold = Self::count_nfts(&self.nfts_offered);
+            let partner_sold = Self::vault_nft_counts(&self.partner_vaults);
+            Self::pay_royalties(self.swap_component, &mut self.partner_vaults, &creator_sold);
+            Self::pay_royalties(self.swap_component, &mut self.creator_vaults, &partner_sold);
+        }
+
+        // Collections opt in with two metadata fields on the NFT resource - `royalty_percentage` (a Decimal, 0 to 100)
+        // and `royalty_recipient` (the account to pay). Where a bundle has more than one collection in it, each one's
+        // cut is worked out on its share of the NFTs, so a collection only earns on what was actually its own.
+        fn pay_royalties(
+            component: ComponentAddress,
+            payer_vaults: &mut HashMap<ResourceAddress, Vault>,
+            sold: &HashMap<ResourceAddress, Decimal>,
+        ) {
+            let total_sold: Decimal = sold.values().fold(Decimal::zero(), |total, count| total + *count);
+            if total_sold.is_zero() {
+                return;
+            }
+
+            let payments: HashMap<ResourceAddress, Decimal> = payer_vaults
+                .iter()
+                .filter(|(resource_address, _)| resource_address.is_fungible())
+                .map(|(resource_address, vault)| (*resource_address, vault.amount()))
+                .collect();
+
+            for (collection, count) in sold.iter() {
+                let royalty = Self::collection_royalty(*collection);
+                if royalty.is_none() {
+                    continue;
+                }
+                let (percentage, recipient) = royalty.unwrap();
+
+                for (resource_address, paid) in payments.iter() {
+                    let amount = *paid * *count / total_sold * percentage / dec!(100);
+                    let royalty_bucket = payer_vaults.get_mut(resource_address).unwrap().take_advanced(
+                        amount,
+                        WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                    );
+                    if royalty_bucket.is_empty() {
+                        royalty_bucket.drop_empty();
+                        continue;
+                    }
+
+                    // a recipient that won't take it just doesn't get paid - the trade still has to go through
+                    let amount = royalty_bucket.amount();
+                    match Global::<Account>::from(recipient).try_deposit_or_refund(royalty_bucket, None) {
+                        Some(refused) => payer_vaults.get_mut(resource_address).unwrap().put(refused),
+                        None => Runtime::emit_event(RoyaltyPaid {
+                            component,
+                            collection: *collection,
+                            resource: *resource_address,
+                            amount,
+                            recipient,
+                        }),
+                    }
+                }
+            }
+        }
+
+        // reads a collection's royalty settings - anything missing, malformed or out of range counts as no royalty,
+        // and so does a recipient that isn't an account
+        fn collection_royalty(collection: ResourceAddress) -> Option<(Decimal, ComponentAddress)> {
+            let resource_manager = ResourceManager::from(collection);
+            let percentage: Option<Decimal> = resource_manager
+                .get_metadata("royalty_percentage")
+                .ok()
+                .flatten();
+            let recipient: Option<GlobalAddress> = resource_manager
+                .get_metadata("royalty_recipient")
+                .ok()
+                .flatten();
+
+            match (percentage, recipient) {
+                (Some(percentage), Some(recipient))
+                    if percentage > Decimal::zero()
+                        && percentage <= dec!(100)
+                        && matches!(
+                            recipient.as_node_id().entity_type(),
+                            Some(EntityType::GlobalAccount)
+                                | Some(EntityType::GlobalVirtualSecp256k1Account)
+                                | Some(EntityType::GlobalVirtualEd25519Account)
+                        ) =>
+                {
+                    Some((percentage, ComponentAddress::new_or_panic(recipient.as_node_id().0)))
+                }
+                _ => None,
+            }
+        }
+
+        fn count_nfts(nfts: &[NonFungibleGlobalId]) -> HashMap<ResourceAddress, Decimal> {
+            let mut counts: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            for nft in nfts.iter() {
+                *counts.entry(nft.resource_address()).or_insert(Decimal::zero()) += Decimal::one();
+            }
+            counts
+        }
+
+        fn vault_nft_counts(vaults: &HashMap<ResourceAddress, Vault>) -> HashMap<ResourceAddress, Decimal> {
+            vaults
+                .iter()
+                .filter(|(resource_address, vault)| !resource_address.is_fungible() && !vault.is_empty())
+                .map(|(resource_address, vault)| (*resource_address, vault.amount()))
+                .collect()
+        }
+
+        // takes the platform's cut of each fungible bucket on its way out of the swap, so whoever receives a fungible
+        // leg pays the fee on it. Uses the fee config the swap was created with, not whatever the registry has now.
+        fn charge_fees(
+            &mut self,
+            buckets: Vec<Bucket>,
+            referral: Option<Referral>,
+            membership: Option<Proof>,
+        ) -> Vec<Bucket> {
+            if self.fee_config.is_none() {
+                return buckets;
+            }
+            let fee_config = self.fee_config.clone().unwrap();
+
+            // a proof of the wrong resource fails rather than quietly paying full price
+            let discount_bps = match (membership, fee_config.membership) {
+                (Some(proof), Some(membership_resource)) => {
+                    fee_config.discount_for(proof.check(membership_resource).amount())
+                }
+                _ => 0,
+            };
+
+            let mut charged: Vec<Bucket> = Vec::new();
+            for mut bucket in buckets.into_iter() {
+                let resource_address = bucket.resource_address();
+                if resource_address.is_fungible() && !bucket.is_empty() {
+                    let mut fee = bucket.take_advanced(
+                        fee_config.fee_for(resource_address, bucket.amount(), discount_bps),
+                        WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                    );
+                    if !fee.is_empty() {
+                        Runtime::emit_event(FeeCharged {
+                            component: self.swap_component,
+                            resource: resource_address,
+                            amount: fee.amount(),
+                        });
+
+                        // the referring frontend's cut goes straight to them - if their account won't take it,
+                        // it just stays with the rest of the fee
+                        if let Some(referral) = referral.as_ref() {
+                            let share_bps = referral.share_bps.min(fee_config.max_referral_bps);
+                            let cut = fee.take_advanced(
+                                fee.amount() * Decimal::from(share_bps) / dec!(10000),
+                                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                            );
+                            let amount = cut.amount();
+                            if amount.is_zero() {
+                                fee.put(cut);
+                            } else {
+                                match Global::<Account>::from(referral.account).try_deposit_or_refund(cut, None) {
+                                    Some(refused) => fee.put(refused),
+                                    None => Runtime::emit_event(ReferralPaid {
+                                        component: self.swap_component,
+                                        referrer: referral.account,
+                                        resource: resource_address,
+                                        amount,
+                                    }),
+                                }
+                            }
+                        }
+                    }
+                    self.fee_vaults
+                        .entry(resource_address)
+                        .or_insert_with(|| Vault::new(resource_address))
+                        .put(fee);
+                }
+                charged.push(bucket);
+            }
+
+            charged
+        }
+
+        fn check_referral(referral: &Option<Referral>) {
+            if let Some(referral) = referral {
+                assert!(referral.share_bps <= 10000, "Referral share can't be more than 100%");
+            }
+        }
+
+        // takes up to `limit` assets out of a set of vaults - a fungible vault is emptied in one go and counts as one,
+        // NFTs count one each
+        fn take_page(vaults: &mut HashMap<ResourceAddress, Vault>, limit: u32) -> Vec<Bucket> {
+            let mut buckets: Vec<Bucket> = Vec::new();
+            let mut remaining = limit;
+
+            let resources: Vec<ResourceAddress> = vaults.keys().cloned().collect();
+            for resource_address in resources.into_iter() {
+                if remaining == 0 {
+                    break;
+                }
+                let vault = vaults.get_mut(&resource_address).unwrap();
+                if vault.is_empty() {
+                    continue;
+                }
+
+                if resource_address.is_fungible() {
+                    buckets.push(vault.take_all());
+                    remaining -= 1;
+                } else {
+                    let mut nft_vault = vault.as_non_fungible();
+                    let ids = nft_vault.non_fungible_local_ids(remaining);
+                    remaining -= ids.len() as u32;
+                    buckets.push(nft_vault.take_non_fungibles(&ids).into());
+                }
+            }
+
+            buckets
+        }
+
+        // checks the partner's badge if the swap was set up as private or holder gated - no-op for public swaps.
+        // Any of the partner keys will do, and the one presented is handed back so we can record who took the swap.
+        fn check_partner_badge(&self, b_badge: Option<Proof>) -> Option<NonFungibleLocalId> {
+            if self.holder_gate.is_some() {
+                assert!(b_badge.is_some(), "Badge required");
+
+                let gate = self.holder_gate.as_ref().unwrap();
+                let checked_proof = b_badge.unwrap().check(gate.resource);
+                assert!(checked_proof.amount() >= gate.amount, "Not holding enough to take this swap");
+
+                return None;
+            }
+
+            if self.badge_partner_locals.is_empty() {
+                return None;
+            }
+
+            // check badge has been passed
+            assert!(b_badge.is_some(), "Badge required");
+
+            // check passes badge is valid
+            let badge_to_validate = b_badge.unwrap();
+            let validation_resource = self.badge_partner.unwrap();
+
+            let badge_local_to_validate = badge_to_validate.check(validation_resource);
+
+            let badge_proof_local = badge_local_to_validate
+                .as_non_fungible()
+                .non_fungible_local_id();
+            assert!(
+                self.badge_partner_locals.contains(&badge_proof_local),
+                "Incorrect badge"
+            );
+
+            Some(badge_proof_local)
+        }
+
+        // whether the partner has deposited everything that was requested
+        fn deposit_complete(&self) -> bool {
+            let deposit = self.partner_deposit.as_ref().unwrap();
+            let nfts_needed = self.expected_nfts.len() > 0 || self.expected_collections.len() > 0;
+            let tokens_needed = self.expected_tokens.len() > 0;
+
+            (deposit.nfts || !nfts_needed) && (deposit.tokens || !tokens_needed)
+        }
+
+        // once the partner side is fully funded the arbiter's dispute window starts, and in approval mode
+        // the swap waits on the creator before the partner can claim
+        fn deposit_received(&mut self) {
+            if !self.deposit_complete() {
+                return;
+            }
+            if self.arbiter.is_some() || self.settlement_delay.is_some() {
+                self.funded_at = Some(Clock::current_time(TimePrecision::Minute));
+            }
+            if self.require_approval {
+                self.set_status(SwapStatus::PendingApproval);
+            }
+        }
+
+        fn in_dispute_window(&self) -> bool {
+            match self.arbiter.as_ref() {
+                Some(arbitration) => self.within_seconds_of_funding(arbitration.dispute_window),
+                None => false,
+            }
+        }
+
+        fn in_settlement_delay(&self) -> bool {
+            match self.settlement_delay {
+                Some(delay) => self.within_seconds_of_funding(delay),
+                None => false,
+            }
+        }
+
+        // neither side can claim or pull out while either window is running
+        fn is_locked(&self) -> bool {
+            self.in_dispute_window() || self.in_settlement_delay()
+        }
+
+        fn within_seconds_of_funding(&self, seconds: i64) -> bool {
+            if self.funded_at.is_none() {
+                return false;
+            }
+            let window_end = self.funded_at.unwrap().add_seconds(seconds).unwrap();
+            Clock::current_time_is_strictly_before(window_end, TimePrecision::Minute)
+        }
+
+        // arbiters can only step in on a funded swap that nobody has settled or cancelled, inside the window -
+        // or any time after it if one of the sides has raised a dispute
+        fn check_arbiter_window(&self) {
+            assert!(
+                matches!(
+                    self.status,
+                    SwapStatus::PartnerFunded | SwapStatus::PendingApproval | SwapStatus::Approved
+                ),
+                "Swap isn't waiting on the arbiter"
+            );
+            assert!(self.funded_at.is_some(), "Partner side isn't fully funded yet");
+            assert!(self.in_dispute_window() || self.disputed, "Dispute window has passed");
+        }
+
+        // mints a receipt for the first deposit, or checks the receipt presented for any follow-up deposit
+        fn bind_depositor(
+            &mut self,
+            deposit_receipt: Option<Proof>,
+            partner_key: Option<NonFungibleLocalId>,
+        ) -> Option<Bucket> {
+            if self.partner_deposit.is_some() {
+                assert!(deposit_receipt.is_some(), "Deposit receipt required");
+                self.check_deposit_receipt(deposit_receipt.unwrap());
+                return None;
+            }
+
+            self.check_no_pending_withdrawal();
+            let receipt = self.mint_deposit_receipt();
+            self.partner_deposit = Some(PartnerDeposit {
+                receipt: receipt.as_non_fungible().non_fungible_local_id(),
+                nfts: false,
+                tokens: false,
+                nfts_received: Vec::new(),
+                tokens_received: HashMap::new(),
+                partner_key,
+            });
+            self.set_status(SwapStatus::PartnerFunded);
+
+            Some(receipt)
+        }
+
+        // unbinds the current deposit, leaving its assets in partner_vaults for the depositor to page back out
+        fn release_deposit(&mut self) {
+            let deposit = self.partner_deposit.take().unwrap();
+            self.pending_withdrawal = Some(deposit.receipt);
+            self.partner_referral = None;
+            self.funded_at = None;
+            self.disputed = false;
+            if matches!(
+                self.status,
+                SwapStatus::PartnerFunded | SwapStatus::PendingApproval | SwapStatus::Approved
+            ) {
+                self.set_status(SwapStatus::Open);
+            }
+        }
+
+        fn take_withdrawal_page(&mut self, receipt_id: &NonFungibleLocalId, limit: u32) -> Vec<Bucket> {
+            assert!(
+                self.pending_withdrawal.as_ref() == Some(receipt_id),
+                "Nothing to withdraw for this receipt"
+            );
+
+            let buckets = Self::take_page(&mut self.partner_vaults, limit);
+            if self.partner_vaults.values().all(|vault| vault.is_empty()) {
+                self.pending_withdrawal = None;
+            }
+            buckets
+        }
+
+        fn check_no_pending_withdrawal(&self) {
+            assert!(
+                self.pending_withdrawal.is_none(),
+                "The last deposit hasn't been withdrawn yet"
+            );
+        }
+
+        fn receipt_id(&self, deposit_receipt: Proof) -> NonFungibleLocalId {
+            deposit_receipt
+                .check(self.deposit_receipt_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+
+        fn mint_deposit_receipt(&self) -> Bucket {
+            self.deposit_receipt_manager.mint_ruid_non_fungible(DepositReceipt {
+                name: "Trove Deposit".to_owned(),
+                description: "This NFT lets you claim or withdraw your deposit on a Trove Swap".to_owned(),
+                key_image_url: Url::of("https://trove.tools/multiple.png"),
+                swap_component: self.swap_component,
+            })
+        }
+
+        fn check_deposit_receipt(&self, deposit_receipt: Proof) {
+            assert!(self.partner_deposit.is_some(), "Nothing has been deposited");
+
+            let receipt_local = deposit_receipt
+                .check(self.deposit_receipt_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id();
+            assert!(
+                receipt_local == self.partner_deposit.as_ref().unwrap().receipt,
+                "Incorrect deposit receipt"
+            );
+        }
+
+        // how many NFTs the collection requests ask for in total - every request has to ask for at least one
+        fn collection_count(requests: &[CollectionRequest]) -> u64 {
+            let mut count: u64 = 0;
+            for request in requests.iter() {
+                assert!(request.amount > 0, "Collection request has to ask for at least one NFT");
+                count = count.checked_add(request.amount).expect("Collection request is too large");
+            }
+            count
+        }
+
+        fn nft_ids(buckets: &[Bucket]) -> Vec<NonFungibleGlobalId> {
+            let mut nft_record: Vec<NonFungibleGlobalId> = Vec::new();
+            for bucket in buckets.iter() {
+                let nft_resource = bucket.resource_address();
+                for nft_id in bucket.as_non_fungible().non_fungible_local_ids().into_iter() {
+                    nft_record.push(NonFungibleGlobalId::new(nft_resource, nft_id))
+                }
+            }
+            nft_record
+        }
+
+        // works out whether the NFTs the partner has put in so far fill the request
+        fn validate_nft_deposit(&self, nft_record: &[NonFungibleGlobalId]) -> bool {
+            let mut complete = self
+                .expected_nfts
+                .iter()
+                .all(|item| nft_record.contains(item));
+
+            // anything not already matched against a specific id counts towards the collection requests. Requests can
+            // overlap (e.g. "1 Ford" and "1 Raptor"), so rather than first come first served each NFT is fitted in with
+            // an augmenting path, moving ones already placed if need be. Anything that still doesn't fit is just extra.
+            let unclaimed: Vec<NonFungibleGlobalId> = nft_record
+                .iter()
+                .filter(|item| !self.expected_nfts.contains(item))
+                .cloned()
+                .collect();
+
+            let eligible: Vec<Vec<usize>> = unclaimed
+                .iter()
+                .map(|item| {
+                    self.expected_collections
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, request)| {
+                            item.resource_address() == request.resource
+                                && Self::nft_has_traits(item, &request.traits)
+                        })
+                        .map(|(index, _)| index)
+                        .collect()
+                })
+                .collect();
+            let capacity: Vec<u64> = self.expected_collections.iter().map(|request| request.amount).collect();
+            let mut assigned: Vec<Option<usize>> = vec![None; unclaimed.len()];
+            let mut load: Vec<u64> = vec![0; capacity.len()];
+
+            for item in 0..unclaimed.len() {
+                let mut visited: Vec<bool> = vec![false; capacity.len()];
+                Self::assign_nft(item, &eligible, &capacity, &mut assigned, &mut load, &mut visited);
+            }
+
+            if load.iter().zip(capacity.iter()).any(|(filled, amount)| filled < amount) {
+                complete = false;
+            }
+
+            complete
+        }
+
+        // finds a collection request for `item` with room left, moving NFTs already placed on to another of their
+        // requests if that's what it takes. `visited` stops a request being tried twice on the same path.
+        fn assign_nft(
+            item: usize,
+            eligible: &[Vec<usize>],
+            capacity: &[u64],
+            assigned: &mut [Option<usize>],
+            load: &mut [u64],
+            visited: &mut [bool],
+        ) -> bool {
+            for request in eligible[item].iter().cloned() {
+                if visited[request] {
+                    continue;
+                }
+                visited[request] = true;
+
+                if load[request] < capacity[request] {
+                    assigned[item] = Some(request);
+                    load[request] += 1;
+                    return true;
+                }
+
+                let holders: Vec<usize> = (0..assigned.len())
+                    .filter(|other| assigned[*other] == Some(request))
+                    .collect();
+                for other in holders.into_iter() {
+                    assigned[other] = None;
+                    if Self::assign_nft(other, eligible, capacity, assigned, load, visited) {
+                        assigned[item] = Some(request);
+                        return true;
+                    }
+                    assigned[other] = Some(request);
+                }
+            }
+
+            false
+        }
+
+        // reads the NFT's data straight off the ledger - we don't know the collection's data struct, so it's checked
+        // field by field as a raw SBOR tuple
+        fn nft_has_traits(nft: &NonFungibleGlobalId, traits: &[TraitPredicate]) -> bool {
+            if traits.is_empty() {
+                return true;
+            }
+
+            let data: ScryptoValue = ResourceManager::from(nft.resource_address()).call(
+                NON_FUNGIBLE_RESOURCE_MANAGER_GET_NON_FUNGIBLE_IDENT,
+                &NonFungibleResourceManagerGetNonFungibleInput {
+                    id: nft.local_id().clone(),
+                },
+            );
+
+            let fields = match data {
+                ScryptoValue::Tuple { fields } => fields,
+                _ => return false,
+            };
+
+            traits
+                .iter()
+                .all(|predicate| match fields.get(predicate.field as usize) {
+                    Some(ScryptoValue::String { value }) => value == &predicate.value,
+                    _ => false,
+                })
+        }
+
+        fn token_amounts(buckets: &[Bucket]) -> HashMap<ResourceAddress, Decimal> {
+            let mut amounts: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            for bucket in buckets.iter() {
+                *amounts.entry(bucket.resource_address()).or_insert(Decimal::zero()) += bucket.amount();
+            }
+            amounts
+        }
+
+        // checks the tokens put in so far are all requested and none over - returns whether they fill the request
+        fn validate_token_deposit(&self, received: &HashMap<ResourceAddress, Decimal>) -> bool {
+            for (resource_address, amount) in received.iter() {
+                let expected = self.expected_tokens.get(resource_address).expect("Token deposits don't match");
+                assert!(amount <= expected, "Token deposits don't match");
+            }
+
+            self.expected_tokens
+                .iter()
+                .all(|(resource_address, expected)| received.get(resource_address) == Some(expected))
+        }
+
+        fn store_partner_assets(&mut self, buckets: Vec<Bucket>) {
+            for bucket in buckets.into_iter() {
+                self.partner_vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+        }
     }
 }
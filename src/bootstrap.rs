@@ -117,6 +117,65 @@ mod bootstrap {
             (barter_component, vec![cars, phones, laptops])
         }
 
+        /// A collection that opts in to Trove's royalty metadata convention, paying `royalty_percentage`
+        /// of any fungible payment for its NFTs to `recipient`. Its NFTs are #1# and #2#.
+        pub fn bootstrap_royalty_collection(recipient: ComponentAddress, royalty_percentage: Decimal) -> NonFungibleBucket {
+            ResourceBuilder::new_integer_non_fungible(OwnerRole::None)
+                .metadata(metadata!(
+                    init {
+                        "name" => "Paintings NFT".to_owned(), locked;
+                        "description" => "Fine art, with a cut for the artist every time it changes hands".to_owned(), locked;
+                        "symbol" => "ART".to_owned(), locked;
+                        "royalty_percentage" => royalty_percentage, locked;
+                        "royalty_recipient" => GlobalAddress::from(recipient), locked;
+                    }
+                ))
+                .mint_initial_supply([
+                    (
+                        IntegerNonFungibleLocalId::new(1),
+                        Painting {
+                            name: "Sunflowers".to_string(),
+                            artist: "Van Gogh".to_string(),
+                        },
+                    ),
+                    (
+                        IntegerNonFungibleLocalId::new(2),
+                        Painting {
+                            name: "Water Lilies".to_string(),
+                            artist: "Monet".to_string(),
+                        },
+                    ),
+                ])
+        }
+
+        /// Two Fords, for requests that overlap - #1# is a Raptor, #2# a Mustang.
+        pub fn bootstrap_fleet() -> NonFungibleBucket {
+            ResourceBuilder::new_integer_non_fungible(OwnerRole::None)
+                .metadata(metadata!(
+                    init {
+                        "name" => "Fleet NFT".to_owned(), locked;
+                        "description" => "A couple of cars from the same manufacturer".to_owned(), locked;
+                        "symbol" => "FLEET".to_owned(), locked;
+                    }
+                ))
+                .mint_initial_supply([
+                    (
+                        IntegerNonFungibleLocalId::new(1),
+                        Car {
+                            name: "Raptor".to_string(),
+                            manufacturer: "Ford".to_string(),
+                        },
+                    ),
+                    (
+                        IntegerNonFungibleLocalId::new(2),
+                        Car {
+                            name: "Mustang".to_string(),
+                            manufacturer: "Ford".to_string(),
+                        },
+                    ),
+                ])
+        }
+
         pub fn local_ids_1(&mut self) -> (ResourceAddress, Vec<NonFungibleLocalId>) {
             let mut new_thing = Vec::new();
 
@@ -156,3 +215,9 @@ struct Laptop {
     name: String,
     manufacturer: String,
 }
+
+#[derive(NonFungibleData, ScryptoSbor)]
+struct Painting {
+    name: String,
+    artist: String,
+}
@@ -0,0 +1,319 @@
+use scrypto::prelude::*;
+use crate::barter::{Escroceipt, SwapStatus};
+
+// A fixed-price swap whose price comes down over time. The seller escrows a bundle and sets a starting price and a floor,
+// and the price falls from one to the other between the listing going up and `ends_at` - smoothly, or in steps if
+// `step_seconds` is set. It sits at the floor after that. First taker to pay the going price gets the bundle.
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ListingCreated {
+    component: ComponentAddress,
+    creator_badge: ResourceAddress,
+    payment_resource: ResourceAddress,
+    start_price: Decimal,
+    floor_price: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ListingTaken {
+    component: ComponentAddress,
+    price: Decimal,
+}
+
+#[blueprint]
+#[events(ListingCreated, ListingTaken)]
+mod dutch {
+
+    enable_package_royalties! {
+        new_dutch_listing => Free;
+        take_listing => Free;
+        seller_claim => Free;
+        seller_cancel => Free;
+        current_price => Free;
+        get_badge => Free;
+        status => Free;
+    }
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+            seller_claim => restrict_to: [admin];
+            seller_cancel => restrict_to: [admin];
+            take_listing => PUBLIC;
+            current_price => PUBLIC;
+            get_badge => PUBLIC;
+            status => PUBLIC;
+        }
+    }
+
+    struct DutchListing {
+        listing_component: ComponentAddress,
+
+        // the bundle and the seller's key
+        creator_vaults: HashMap<ResourceAddress, Vault>,
+        a_vault_key: ResourceAddress,
+        a_vault_key_id: NonFungibleLocalId,
+
+        // pricing
+        payment_resource: ResourceAddress,
+        // the going price is rounded up to this, so it can always be taken out of the payment exactly
+        payment_divisibility: u8,
+        start_price: Decimal,
+        floor_price: Decimal,
+        starts_at: Instant,
+        ends_at: Instant,
+        step_seconds: Option<i64>,
+
+        // what the taker paid, waiting for the seller
+        proceeds: Vault,
+        status: SwapStatus,
+    }
+
+    impl DutchListing {
+        pub fn new_dutch_listing(
+            // Just a random name that users can give their listings
+            custom_trade_name: String,
+            // Optional to include a variety of fungibles in the bundle
+            a_tokens: Option<Vec<Bucket>>,
+            // Optional to include a variety of non fungibles in the bundle
+            a_nfts: Option<Vec<Bucket>>,
+            // What the taker pays in
+            payment_resource: ResourceAddress,
+            // Price when the listing goes up
+            start_price: Decimal,
+            // Lowest the price goes
+            floor_price: Decimal,
+            // When the price hits the floor
+            ends_at: Instant,
+            // Optional to drop the price in steps of this many seconds rather than continuously
+            step_seconds: Option<i64>,
+        ) -> (Global<DutchListing>, NonFungibleBucket) {
+            assert!(a_nfts.is_some() || a_tokens.is_some(), "You need to offer something");
+            assert!(payment_resource.is_fungible(), "Payment has to be fungible");
+            assert!(
+                floor_price >= Decimal::zero() && floor_price <= start_price,
+                "Floor has to be between zero and the starting price"
+            );
+            let starts_at = Clock::current_time(TimePrecision::Minute);
+            assert!(
+                starts_at.seconds_since_unix_epoch < ends_at.seconds_since_unix_epoch,
+                "Listing would end before it starts"
+            );
+            if step_seconds.is_some() {
+                assert!(step_seconds.unwrap() > 0, "Step has to be positive");
+            }
+            let payment_divisibility = match ResourceManager::from(payment_resource).resource_type() {
+                ResourceType::Fungible { divisibility } => divisibility,
+                _ => panic!("Payment has to be fungible"),
+            };
+            // with both ends whole in the payment resource, rounding the going price up never takes it past the start
+            for price in [start_price, floor_price] {
+                assert!(
+                    price.checked_round(payment_divisibility, RoundingMode::ToZero) == Some(price),
+                    "Price has more decimal places than the payment resource allows"
+                );
+            }
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(DutchListing::blueprint_id());
+
+            // same bundle handling and 50 asset cap as Barter
+            let mut nfts_offered: Vec<NonFungibleGlobalId> = Vec::new();
+            let mut tokens_offered: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            let mut creator_vaults: HashMap<ResourceAddress, Vault> = HashMap::new();
+            for bucket in a_tokens.unwrap_or(Vec::new()).into_iter() {
+                *tokens_offered
+                    .entry(bucket.resource_address())
+                    .or_insert(Decimal::zero()) += bucket.amount();
+                creator_vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+            for bucket in a_nfts.unwrap_or(Vec::new()).into_iter() {
+                let resource = bucket.resource_address();
+                for nft_id in bucket.as_non_fungible().non_fungible_local_ids().into_iter() {
+                    nfts_offered.push(NonFungibleGlobalId::new(resource, nft_id))
+                }
+                creator_vaults
+                    .entry(resource)
+                    .or_insert_with(|| Vault::new(resource))
+                    .put(bucket)
+            }
+            assert!(
+                nfts_offered.len() + tokens_offered.len() <= 50,
+                "Reached single transaction event limit"
+            );
+
+            let key_custom_label = String::from("TROVE Key: ") + &custom_trade_name;
+
+            let mut tokens_requested: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            tokens_requested.insert(payment_resource, start_price);
+
+            let a_key = ResourceBuilder::new_string_non_fungible::<Escroceipt>(OwnerRole::None)
+                .metadata(metadata! {
+                    roles {
+                        metadata_locker => rule!(deny_all);
+                        metadata_locker_updater => rule!(deny_all);
+                        metadata_setter => rule!(deny_all);
+                        metadata_setter_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => key_custom_label, locked;
+                        "description" => "Your Dutch Listing on trove.tools".to_owned(), locked;
+                        "key_image_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                })
+                .mint_roles(mint_roles!(
+                    minter => rule!(deny_all);
+                    minter_updater => rule!(deny_all);
+                ))
+                .burn_roles(burn_roles!(
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                ))
+                .mint_initial_supply(vec![(
+                    "Trove_Creator_Key".try_into().unwrap(),
+                    Escroceipt {
+                        name: "Trove Dutch Listing".to_owned(),
+                        description: "This NFT contains details of your Dutch Listing on Trove".to_owned(),
+                        key_image_url: Url::of("https://trove.tools/multiple.png"),
+                        swap_component: component_address,
+                        nfts_offered,
+                        tokens_offered,
+                        nfts_requested: Vec::new(),
+                        collections_requested: Vec::new(),
+                        tokens_requested,
+                    },
+                )]);
+
+            let global_key_id = NonFungibleGlobalId::new(
+                a_key.resource_address(),
+                a_key.as_non_fungible().non_fungible_local_id(),
+            );
+
+            Runtime::emit_event(ListingCreated {
+                component: component_address,
+                creator_badge: a_key.resource_address(),
+                payment_resource,
+                start_price,
+                floor_price,
+            });
+
+            let listing_component = Self {
+                listing_component: component_address,
+                creator_vaults,
+                a_vault_key: a_key.resource_address(),
+                a_vault_key_id: a_key.as_non_fungible().non_fungible_local_id(),
+                payment_resource,
+                payment_divisibility,
+                start_price,
+                floor_price,
+                starts_at,
+                ends_at,
+                step_seconds,
+                proceeds: Vault::new(payment_resource),
+                status: SwapStatus::Open,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .metadata(metadata! (
+                roles {
+                    metadata_setter => rule!(deny_all);
+                    metadata_setter_updater => rule!(deny_all);
+                    metadata_locker => rule!(deny_all);
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name" => "Trove Dutch Listing".to_owned(), locked;
+                    "description" => "Find your listing on https://trove.tools".to_owned(), locked;
+                    "tags" => vec!["Listing".to_string()], locked;
+                    "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                }
+            ))
+            .roles(roles!(
+                admin => rule!(require(global_key_id));
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (listing_component, a_key)
+        }
+
+        /// Pays the going price and takes the bundle. Anything paid over the price comes back in the same call.
+
+        pub fn take_listing(&mut self, mut payment: Bucket) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Open, "Listing is closed");
+            assert!(payment.resource_address() == self.payment_resource, "Wrong payment resource");
+
+            let price = self.current_price();
+            assert!(payment.amount() >= price, "Payment is below the current price");
+            self.proceeds.put(payment.take(price));
+            self.status = SwapStatus::Settled;
+
+            Runtime::emit_event(ListingTaken {
+                component: self.listing_component,
+                price,
+            });
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.creator_vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+            buckets.push(payment);
+
+            return buckets;
+        }
+
+        pub fn seller_claim(&mut self) -> Bucket {
+            assert!(self.status == SwapStatus::Settled, "Listing hasn't been taken yet");
+            self.proceeds.take_all()
+        }
+
+        pub fn seller_cancel(&mut self) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Open, "Listing is closed");
+            self.status = SwapStatus::Cancelled;
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.creator_vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+
+            return buckets;
+        }
+
+        // price right now, going by the ledger clock - rounded up to what the payment resource can actually be split into
+        pub fn current_price(&self) -> Decimal {
+            let now = Clock::current_time(TimePrecision::Minute).seconds_since_unix_epoch;
+            let start = self.starts_at.seconds_since_unix_epoch;
+            let end = self.ends_at.seconds_since_unix_epoch;
+            if now >= end {
+                return self.floor_price;
+            }
+
+            // stepwise listings only count time in whole steps
+            let mut elapsed = now - start;
+            if self.step_seconds.is_some() {
+                let step = self.step_seconds.unwrap();
+                elapsed = elapsed - elapsed % step;
+            }
+
+            let drop = (self.start_price - self.floor_price) * Decimal::from(elapsed) / Decimal::from(end - start);
+            (self.start_price - drop)
+                .checked_round(self.payment_divisibility, RoundingMode::ToPositiveInfinity)
+                .unwrap()
+        }
+
+        // creator key resource and local id
+        pub fn get_badge(&self) -> (ResourceAddress, NonFungibleLocalId) {
+            (self.a_vault_key, self.a_vault_key_id.clone())
+        }
+
+        pub fn status(&self) -> SwapStatus {
+            self.status.clone()
+        }
+    }
+}
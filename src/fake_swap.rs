@@ -0,0 +1,31 @@
+use scrypto::prelude::*;
+
+#[blueprint]
+mod fake_swap {
+    /// Only here for the tests - looks like a swap to RingSettlement, handing over whatever it was made with
+    /// and keeping whatever it's sent, without any of a real swap's checks.
+    struct FakeSwap {
+        vaults: Vec<Vault>,
+    }
+
+    impl FakeSwap {
+        pub fn instantiate(assets: Vec<Bucket>) -> Global<FakeSwap> {
+            Self {
+                vaults: assets.into_iter().map(|bucket| Vault::with_bucket(bucket)).collect(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .globalize()
+        }
+
+        pub fn ring_release(&mut self) -> Vec<Bucket> {
+            self.vaults.iter_mut().map(|vault| vault.take_all()).collect()
+        }
+
+        pub fn ring_fill(&mut self, b_assets: Vec<Bucket>) {
+            for bucket in b_assets.into_iter() {
+                self.vaults.push(Vault::with_bucket(bucket));
+            }
+        }
+    }
+}
@@ -1,2 +1,9 @@
+mod auction;
 mod barter;
 mod bootstrap;
+mod dutch;
+mod fake_swap;
+mod multi_barter;
+mod registry;
+mod ring;
+mod sealed_auction;
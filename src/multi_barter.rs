@@ -0,0 +1,396 @@
+use scrypto::prelude::*;
+use crate::barter::{account_owner_rule, SwapStatus};
+
+// Barter is strictly creator <-> partner. This is for when more than two people are in on it - each named participant
+// commits a bundle, and the legs say who each part of it goes to. Nothing moves until every participant has deposited
+// exactly what their legs send, and up until then anyone can pull their deposit back out.
+
+// Each participant gets one of these - it's what they deposit, claim and cancel with.
+#[derive(NonFungibleData, ScryptoSbor, Debug)]
+pub struct ParticipantKey {
+    name: String,
+    description: String,
+    key_image_url: Url,
+    swap_component: ComponentAddress,
+    participant: u32,
+}
+
+// A single transfer in the swap - `from` and `to` are positions in the participant list.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct Leg {
+    pub from: u32,
+    pub to: u32,
+    pub nfts: Vec<NonFungibleGlobalId>,
+    pub tokens: HashMap<ResourceAddress, Decimal>,
+}
+
+#[derive(ScryptoSbor)]
+pub struct Participant {
+    account: ComponentAddress,
+    vaults: HashMap<ResourceAddress, Vault>,
+    deposited: bool,
+    claimed: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct MultiSwapCreated {
+    component: ComponentAddress,
+    participants: Vec<ComponentAddress>,
+    key_resource: ResourceAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ParticipantDeposited {
+    component: ComponentAddress,
+    participant: u32,
+    deposited: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct MultiSwapSettled {
+    component: ComponentAddress,
+}
+
+#[blueprint]
+#[events(MultiSwapCreated, ParticipantDeposited, MultiSwapSettled)]
+mod multi_barter {
+
+    enable_package_royalties! {
+        new_multi_swap => Free;
+        participant_deposit => Free;
+        participant_cancel => Free;
+        participant_claim => Free;
+        participant_claim_key => Free;
+        get_legs => Free;
+        get_status => Free;
+    }
+
+    struct MultiBarter {
+        swap_component: ComponentAddress,
+        participant_key: ResourceAddress,
+        participant_key_locals: Vec<NonFungibleLocalId>,
+        participants: KeyValueStore<u32, Participant>,
+        // participant keys the participant's account wouldn't accept, waiting to be picked up
+        unclaimed_keys: KeyValueStore<ComponentAddress, Vault>,
+        legs: Vec<Leg>,
+        deposits_outstanding: u32,
+        status: SwapStatus,
+    }
+
+    impl MultiBarter {
+        pub fn new_multi_swap(
+            custom_trade_name: String,
+            participants: Vec<ComponentAddress>,
+            legs: Vec<Leg>,
+        ) -> Global<MultiBarter> {
+            assert!(participants.len() > 2, "Use a regular Barter swap for two people");
+            for (index, account_address) in participants.iter().enumerate() {
+                assert!(
+                    !participants[..index].contains(account_address),
+                    "Duplicate participant account"
+                );
+            }
+
+            // same 50 asset cap as Barter - every leg's assets plus a key per participant
+            let mut asset_count = participants.len();
+            let mut nfts_seen: Vec<NonFungibleGlobalId> = Vec::new();
+            for leg in legs.iter() {
+                assert!(
+                    (leg.from as usize) < participants.len() && (leg.to as usize) < participants.len(),
+                    "Leg refers to an unknown participant"
+                );
+                assert!(leg.from != leg.to, "Leg sends assets back to the sender");
+                assert!(!leg.nfts.is_empty() || !leg.tokens.is_empty(), "Leg doesn't send anything");
+                for nft in leg.nfts.iter() {
+                    assert!(!nfts_seen.contains(nft), "NFT is sent in more than one leg");
+                    nfts_seen.push(nft.clone());
+                }
+                for amount in leg.tokens.values() {
+                    assert!(*amount > Decimal::zero(), "Leg token amounts must be positive");
+                }
+                asset_count = asset_count + leg.nfts.len() + leg.tokens.len();
+            }
+            assert!(asset_count <= 50, "Reached single transaction event limit");
+            for index in 0..participants.len() {
+                assert!(
+                    legs.iter().any(|leg| leg.from as usize == index),
+                    "Every participant needs to send something"
+                );
+            }
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(MultiBarter::blueprint_id());
+
+            let key_custom_label = String::from("TROVE Key: ") + &custom_trade_name;
+
+            let mut keys: Vec<(StringNonFungibleLocalId, ParticipantKey)> = Vec::new();
+            for (index, _) in participants.iter().enumerate() {
+                keys.push((
+                    StringNonFungibleLocalId::new(format!("Trove_Participant_Key_{}", index + 1)).unwrap(),
+                    ParticipantKey {
+                        name: "Trove Multi Swap".to_owned(),
+                        description: "This NFT lets you deposit into and claim from a multi-party Swap on Trove".to_owned(),
+                        key_image_url: Url::of("https://trove.tools/multiple.png"),
+                        swap_component: component_address,
+                        participant: index as u32,
+                    },
+                ));
+            }
+
+            let mut participant_keys = ResourceBuilder::new_string_non_fungible::<ParticipantKey>(OwnerRole::None)
+                .metadata(metadata! {
+                    roles {
+                        metadata_locker => rule!(deny_all);
+                        metadata_locker_updater => rule!(deny_all);
+                        metadata_setter => rule!(deny_all);
+                        metadata_setter_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => key_custom_label, locked;
+                        "description" => "Your Multi Swap on trove.tools".to_owned(), locked;
+                        "key_image_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                })
+                .mint_roles(mint_roles!(
+                    minter => rule!(deny_all);
+                    minter_updater => rule!(deny_all);
+                ))
+                .burn_roles(burn_roles!(
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                ))
+                .mint_initial_supply(keys);
+
+            let key_resource = participant_keys.resource_address();
+            let participant_store: KeyValueStore<u32, Participant> = KeyValueStore::new();
+            let unclaimed_keys: KeyValueStore<ComponentAddress, Vault> = KeyValueStore::new();
+            let mut participant_key_locals: Vec<NonFungibleLocalId> = Vec::new();
+
+            for (index, account_address) in participants.iter().enumerate() {
+                let local_id = NonFungibleLocalId::String(
+                    StringNonFungibleLocalId::new(format!("Trove_Participant_Key_{}", index + 1)).unwrap(),
+                );
+                let key: Vec<Bucket> = vec![participant_keys.take_non_fungible(&local_id).into()];
+
+                // as with Barter's partner keys, a refused key is held here for `participant_claim_key`
+                let refused_keys = Global::<Account>::from(*account_address)
+                    .try_deposit_batch_or_refund(key, None);
+                if let Some(refused_keys) = refused_keys {
+                    let mut key_vault = Vault::new(key_resource);
+                    for key in refused_keys.into_iter() {
+                        key_vault.put(key)
+                    }
+                    unclaimed_keys.insert(*account_address, key_vault);
+                }
+
+                participant_store.insert(
+                    index as u32,
+                    Participant {
+                        account: *account_address,
+                        vaults: HashMap::new(),
+                        deposited: false,
+                        claimed: false,
+                    },
+                );
+                participant_key_locals.push(local_id);
+            }
+            participant_keys.drop_empty();
+
+            Runtime::emit_event(MultiSwapCreated {
+                component: component_address,
+                participants: participants.clone(),
+                key_resource,
+            });
+
+            Self {
+                swap_component: component_address,
+                participant_key: key_resource,
+                participant_key_locals,
+                participants: participant_store,
+                unclaimed_keys,
+                legs,
+                deposits_outstanding: participants.len() as u32,
+                status: SwapStatus::Open,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .metadata(metadata! (
+                roles {
+                    metadata_setter => rule!(deny_all);
+                    metadata_setter_updater => rule!(deny_all);
+                    metadata_locker => rule!(deny_all);
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name" => "Trove Multi Swap".to_owned(), locked;
+                    "description" => "Find your swap proposal on https://trove.tools".to_owned(), locked;
+                    "tags" => vec!["Swap".to_string()], locked;
+                    "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                }
+            ))
+            .with_address(address_reservation)
+            .globalize()
+        }
+
+        /// Same as Barter's `partner_claim_key` - signed by the named account's owner keys, or with its owner badge if
+        /// it's been securified. There's no creator to reclaim a key for an account under any other owner rule, but as
+        /// nothing moves until everyone's deposited, the worst it does is stall the swap - the rest can still cancel.
+
+        pub fn participant_claim_key(&mut self, account: ComponentAddress) -> Bucket {
+            Runtime::assert_access_rule(account_owner_rule(account));
+
+            let mut key_vault = self
+                .unclaimed_keys
+                .get_mut(&account)
+                .expect("No participant key held for this account");
+            key_vault.take_all()
+        }
+
+        /// Deposits everything the participant's legs send, in one go. The last deposit in settles the swap.
+
+        pub fn participant_deposit(&mut self, assets: Vec<Bucket>, key: Proof) {
+            assert!(self.status == SwapStatus::Open, "Swap is closed");
+            let index = self.check_participant_key(key);
+
+            let mut participant = self.participants.get_mut(&index).unwrap();
+            assert!(!participant.deposited, "Already deposited");
+
+            let mut expected_nfts: Vec<NonFungibleGlobalId> = Vec::new();
+            let mut expected_tokens: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            for leg in self.legs.iter().filter(|leg| leg.from == index) {
+                expected_nfts.extend(leg.nfts.iter().cloned());
+                for (resource_address, amount) in leg.tokens.iter() {
+                    *expected_tokens.entry(*resource_address).or_insert(Decimal::zero()) += *amount;
+                }
+            }
+
+            let mut nft_record: Vec<NonFungibleGlobalId> = Vec::new();
+            let mut token_record: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            for bucket in assets.iter() {
+                let resource = bucket.resource_address();
+                if resource.is_fungible() {
+                    *token_record.entry(resource).or_insert(Decimal::zero()) += bucket.amount();
+                } else {
+                    for nft_id in bucket.as_non_fungible().non_fungible_local_ids().into_iter() {
+                        nft_record.push(NonFungibleGlobalId::new(resource, nft_id))
+                    }
+                }
+            }
+            assert!(
+                nft_record.len() == expected_nfts.len()
+                    && expected_nfts.iter().all(|item| nft_record.contains(item)),
+                "no match: {:?} vs {:?}",
+                nft_record,
+                expected_nfts
+            );
+            assert!(token_record == expected_tokens, "Token deposits don't match");
+
+            for bucket in assets.into_iter() {
+                participant
+                    .vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+            participant.deposited = true;
+            drop(participant);
+
+            self.deposits_outstanding -= 1;
+
+            Runtime::emit_event(ParticipantDeposited {
+                component: self.swap_component,
+                participant: index,
+                deposited: true,
+            });
+
+            if self.deposits_outstanding == 0 {
+                self.status = SwapStatus::Settled;
+                Runtime::emit_event(MultiSwapSettled {
+                    component: self.swap_component,
+                });
+            }
+        }
+
+        // pull a deposit back out - only until everyone's in
+        pub fn participant_cancel(&mut self, key: Proof) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Open, "Swap has occurred already occured");
+            let index = self.check_participant_key(key);
+
+            let mut participant = self.participants.get_mut(&index).unwrap();
+            assert!(participant.deposited, "Nothing has been deposited");
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in participant.vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+            participant.deposited = false;
+            drop(participant);
+
+            self.deposits_outstanding += 1;
+
+            Runtime::emit_event(ParticipantDeposited {
+                component: self.swap_component,
+                participant: index,
+                deposited: false,
+            });
+
+            return buckets;
+        }
+
+        // once settled, each participant collects every leg sent to them
+        pub fn participant_claim(&mut self, key: Proof) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Settled, "Swap hasn't occurred yet");
+            let index = self.check_participant_key(key);
+
+            let mut claimant = self.participants.get_mut(&index).unwrap();
+            assert!(!claimant.claimed, "Already claimed");
+            claimant.claimed = true;
+            drop(claimant);
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for leg in self.legs.iter().filter(|leg| leg.to == index) {
+                let mut sender = self.participants.get_mut(&leg.from).unwrap();
+
+                let mut nfts_by_resource: HashMap<ResourceAddress, IndexSet<NonFungibleLocalId>> = HashMap::new();
+                for nft in leg.nfts.iter() {
+                    nfts_by_resource
+                        .entry(nft.resource_address())
+                        .or_insert(IndexSet::new())
+                        .insert(nft.local_id().clone());
+                }
+                for (resource_address, ids) in nfts_by_resource.iter() {
+                    let vault = sender.vaults.get_mut(resource_address).unwrap();
+                    buckets.push(vault.as_non_fungible().take_non_fungibles(ids).into())
+                }
+                for (resource_address, amount) in leg.tokens.iter() {
+                    let vault = sender.vaults.get_mut(resource_address).unwrap();
+                    buckets.push(vault.take(*amount))
+                }
+            }
+
+            return buckets;
+        }
+
+        pub fn get_legs(&self) -> Vec<Leg> {
+            self.legs.clone()
+        }
+
+        pub fn get_status(&self) -> (SwapStatus, u32) {
+            (self.status.clone(), self.deposits_outstanding)
+        }
+
+        // returns the participant's position in the list
+        fn check_participant_key(&self, key: Proof) -> u32 {
+            let key_local = key
+                .check(self.participant_key)
+                .as_non_fungible()
+                .non_fungible_local_id();
+
+            self.participant_key_locals
+                .iter()
+                .position(|local| local == &key_local)
+                .expect("Incorrect key") as u32
+        }
+    }
+}
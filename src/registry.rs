@@ -0,0 +1,405 @@
+use scrypto::prelude::*;
+use crate::barter::*;
+use crate::barter::barter::Barter;
+
+// One place to find every swap on Trove, rather than scraping ComponentCreated events off the ledger.
+// Swaps created through here call back in whenever their status changes, so the indexes below stay current.
+
+// The platform fee on swaps created through the registry - `bps` of every fungible leg. Legs paid in `fee_resource`
+// (XRD, usually) are also held to `minimum` and `maximum`, which are in its units - any other resource just pays `bps`.
+// Each side pays on the fungibles it receives, when it claims them, and the frontend that brought that side in
+// can be given up to `max_referral_bps` of it.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct FeeConfig {
+    pub bps: u16,
+    pub fee_resource: ResourceAddress,
+    pub minimum: Decimal,
+    pub maximum: Decimal,
+    pub receiver: ComponentAddress,
+    // most a referring frontend can be given out of each fee
+    pub max_referral_bps: u16,
+    // holders of the membership resource get a discount off whatever they'd otherwise pay
+    pub membership: Option<ResourceAddress>,
+    pub discount_tiers: Vec<DiscountTier>,
+}
+
+// Presenting a proof of at least `min_held` of the membership resource takes `discount_bps` off the fee -
+// the best tier the proof qualifies for applies, so 10000 means members don't pay at all.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct DiscountTier {
+    pub min_held: Decimal,
+    pub discount_bps: u16,
+}
+
+impl FeeConfig {
+    pub fn fee_for(&self, resource: ResourceAddress, amount: Decimal, discount_bps: u16) -> Decimal {
+        let mut fee = amount * Decimal::from(self.bps) / dec!(10000);
+        if resource == self.fee_resource {
+            fee = fee.max(self.minimum).min(self.maximum).min(amount);
+        }
+        fee * Decimal::from(10000 - discount_bps) / dec!(10000)
+    }
+
+    pub fn discount_for(&self, held: Decimal) -> u16 {
+        self.discount_tiers
+            .iter()
+            .filter(|tier| held >= tier.min_held)
+            .map(|tier| tier.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct SwapRegistered {
+    component: ComponentAddress,
+    creator_badge: ResourceAddress,
+}
+
+#[blueprint]
+#[events(SwapRegistered)]
+mod registry {
+
+    enable_package_royalties! {
+        instantiate_registry => Free;
+        new_trade_proposal => Free;
+        new_draft => Free;
+        record_status => Free;
+        get_swap_status => Free;
+        get_by_creator_key => Free;
+        get_by_offered => Free;
+        get_by_requested => Free;
+        get_by_status => Free;
+        fee_config => Free;
+        set_fee_config => Free;
+        collect_fees => Free;
+    }
+
+    enable_method_auth! {
+        methods {
+            new_trade_proposal => PUBLIC;
+            new_draft => PUBLIC;
+            record_status => PUBLIC;
+            get_swap_status => PUBLIC;
+            get_by_creator_key => PUBLIC;
+            get_by_offered => PUBLIC;
+            get_by_requested => PUBLIC;
+            get_by_status => PUBLIC;
+            fee_config => PUBLIC;
+            set_fee_config => restrict_to: [OWNER];
+            collect_fees => restrict_to: [OWNER];
+        }
+    }
+
+    struct TroveRegistry {
+        registry_component: ComponentAddress,
+
+        // every swap created through here and where it's at
+        swaps: KeyValueStore<ComponentAddress, SwapStatus>,
+
+        // each swap mints its own creator key resource, so this one's one-to-one
+        by_creator_key: KeyValueStore<ResourceAddress, ComponentAddress>,
+
+        // The rest are lists, kept one entry per (key, position) with a count per key, so nothing grows with the
+        // number of swaps under a key - creating swaps is free, and one big list would eventually be too big to load.
+        // Swaps only ever move between statuses, so that index also keeps where each swap sits to swap it out.
+        by_offered: KeyValueStore<(ResourceAddress, u64), ComponentAddress>,
+        offered_counts: KeyValueStore<ResourceAddress, u64>,
+        by_requested: KeyValueStore<(ResourceAddress, u64), ComponentAddress>,
+        requested_counts: KeyValueStore<ResourceAddress, u64>,
+        by_status: KeyValueStore<(SwapStatus, u64), ComponentAddress>,
+        status_counts: KeyValueStore<SwapStatus, u64>,
+        status_positions: KeyValueStore<ComponentAddress, u64>,
+
+        // platform fee - each swap keeps a copy of whatever this was when it was created
+        fee_config: Option<FeeConfig>,
+    }
+
+    impl TroveRegistry {
+        /// Returns the registry and its owner badge, which sets the fee config and collects fees.
+
+        pub fn instantiate_registry(fee_config: Option<FeeConfig>) -> (Global<TroveRegistry>, Bucket) {
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(TroveRegistry::blueprint_id());
+
+            if fee_config.is_some() {
+                Self::check_fee_config(fee_config.as_ref().unwrap());
+            }
+
+            let owner_badge = ResourceBuilder::new_fungible(OwnerRole::None)
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata(metadata!(
+                    init {
+                        "name" => "Trove Registry Owner".to_owned(), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                ))
+                .mint_initial_supply(1);
+
+            let registry = Self {
+                registry_component: component_address,
+                swaps: KeyValueStore::new(),
+                by_creator_key: KeyValueStore::new(),
+                by_offered: KeyValueStore::new(),
+                offered_counts: KeyValueStore::new(),
+                by_requested: KeyValueStore::new(),
+                requested_counts: KeyValueStore::new(),
+                by_status: KeyValueStore::new(),
+                status_counts: KeyValueStore::new(),
+                status_positions: KeyValueStore::new(),
+                fee_config,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::Fixed(rule!(require(owner_badge.resource_address()))))
+            .metadata(metadata! (
+                roles {
+                    metadata_setter => rule!(deny_all);
+                    metadata_setter_updater => rule!(deny_all);
+                    metadata_locker => rule!(deny_all);
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name" => "Trove Registry".to_owned(), locked;
+                    "description" => "Every swap proposal on https://trove.tools".to_owned(), locked;
+                    "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                }
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (registry, owner_badge.into())
+        }
+
+        /// Same arguments as `Barter::new_trade_proposal` - the swap is created as normal, the creator gets their key back,
+        /// and the registry starts tracking it.
+
+        pub fn new_trade_proposal(
+            &mut self,
+            custom_trade_name: String,
+            partners: Option<Vec<ComponentAddress>>,
+            a_tokens: Option<Vec<Bucket>>,
+            a_nfts: Option<Vec<Bucket>>,
+            b_nft_deposits: Option<Vec<NonFungibleGlobalId>>,
+            b_collection_deposits: Option<Vec<CollectionRequest>>,
+            b_token_deposits: Option<HashMap<ResourceAddress, Decimal>>,
+            options: ProposalOptions,
+        ) -> (Global<Barter>, NonFungibleBucket) {
+            let (swap, creator_key) = Blueprint::<Barter>::new_trade_proposal(
+                custom_trade_name,
+                partners,
+                a_tokens,
+                a_nfts,
+                b_nft_deposits,
+                b_collection_deposits,
+                b_token_deposits,
+                Some(self.registry_link()),
+                options,
+            );
+            self.register(swap.get_proposal());
+
+            (swap, creator_key)
+        }
+
+        // drafts are indexed by creator key and status straight away, and by resource once they're published
+        pub fn new_draft(
+            &mut self,
+            custom_trade_name: String,
+            partners: Option<Vec<ComponentAddress>>,
+            options: ProposalOptions,
+        ) -> (Global<Barter>, NonFungibleBucket) {
+            let (swap, creator_key) = Blueprint::<Barter>::new_draft(
+                custom_trade_name,
+                partners,
+                Some(self.registry_link()),
+                options,
+            );
+            self.register(swap.get_proposal());
+
+            (swap, creator_key)
+        }
+
+        /// Called by each swap whenever its status changes. Only the swap itself can report its own status,
+        /// and anything that wasn't created through this registry is ignored.
+
+        pub fn record_status(&mut self, proposal: ProposalView) {
+            Runtime::assert_access_rule(rule!(require(global_caller(proposal.component))));
+
+            let previous = match self.swaps.get(&proposal.component) {
+                Some(status) => status.clone(),
+                None => return,
+            };
+
+            self.remove_from_status(previous.clone(), proposal.component);
+            self.add_to_status(proposal.status.clone(), proposal.component);
+            self.swaps.insert(proposal.component, proposal.status.clone());
+
+            if previous == SwapStatus::Draft && proposal.status == SwapStatus::Open {
+                self.index_resources(&proposal);
+            }
+        }
+
+        pub fn get_swap_status(&self, component: ComponentAddress) -> Option<SwapStatus> {
+            self.swaps.get(&component).map(|status| status.clone())
+        }
+
+        pub fn get_by_creator_key(&self, creator_badge: ResourceAddress) -> Option<ComponentAddress> {
+            self.by_creator_key.get(&creator_badge).map(|component| *component)
+        }
+
+        // the list getters are paged - up to `limit` swaps from position `start`, along with how many there are in total
+
+        pub fn get_by_offered(&self, resource: ResourceAddress, start: u64, limit: u64) -> (Vec<ComponentAddress>, u64) {
+            Self::read_index(&self.by_offered, &self.offered_counts, resource, start, limit)
+        }
+
+        pub fn get_by_requested(&self, resource: ResourceAddress, start: u64, limit: u64) -> (Vec<ComponentAddress>, u64) {
+            Self::read_index(&self.by_requested, &self.requested_counts, resource, start, limit)
+        }
+
+        pub fn get_by_status(&self, status: SwapStatus, start: u64, limit: u64) -> (Vec<ComponentAddress>, u64) {
+            Self::read_index(&self.by_status, &self.status_counts, status, start, limit)
+        }
+
+        pub fn fee_config(&self) -> Option<FeeConfig> {
+            self.fee_config.clone()
+        }
+
+        // None turns fees off. Only applies to swaps created from here on - existing swaps keep the config they were made with
+        pub fn set_fee_config(&mut self, fee_config: Option<FeeConfig>) {
+            if fee_config.is_some() {
+                Self::check_fee_config(fee_config.as_ref().unwrap());
+            }
+            self.fee_config = fee_config;
+        }
+
+        /// Sweeps the fees each swap has collected so far out to the fee receiver.
+
+        pub fn collect_fees(&mut self, swaps: Vec<ComponentAddress>) {
+            let receiver = self.fee_config.as_ref().expect("No fee config set").receiver;
+
+            for swap in swaps.into_iter() {
+                assert!(self.swaps.get(&swap).is_some(), "Swap wasn't created through this registry");
+                let fees = Global::<Barter>::from(swap).withdraw_fees();
+                if !fees.is_empty() {
+                    Global::<Account>::from(receiver).try_deposit_batch_or_abort(fees, None);
+                }
+            }
+        }
+
+        fn check_fee_config(fee_config: &FeeConfig) {
+            assert!(fee_config.bps <= 10000, "Fee can't be more than 100%");
+            assert!(fee_config.fee_resource.is_fungible(), "Fee resource has to be fungible");
+            assert!(
+                fee_config.minimum >= Decimal::zero() && fee_config.minimum <= fee_config.maximum,
+                "Fee minimum has to be between zero and the maximum"
+            );
+            assert!(
+                fee_config.max_referral_bps <= 10000,
+                "Referral share can't be more than 100%"
+            );
+            assert!(
+                fee_config.discount_tiers.is_empty() || fee_config.membership.is_some(),
+                "Discount tiers need a membership resource"
+            );
+            for tier in fee_config.discount_tiers.iter() {
+                assert!(tier.discount_bps <= 10000, "Discount can't be more than 100%");
+            }
+        }
+
+        fn registry_link(&self) -> RegistryLink {
+            RegistryLink {
+                component: self.registry_component,
+                fee_config: self.fee_config.clone(),
+            }
+        }
+
+        fn register(&mut self, proposal: ProposalView) {
+            let creator_badge = proposal.creator_badge.resource_address();
+
+            self.swaps.insert(proposal.component, proposal.status.clone());
+            self.by_creator_key.insert(creator_badge, proposal.component);
+            self.add_to_status(proposal.status.clone(), proposal.component);
+            if proposal.status != SwapStatus::Draft {
+                self.index_resources(&proposal);
+            }
+
+            Runtime::emit_event(SwapRegistered {
+                component: proposal.component,
+                creator_badge,
+            });
+        }
+
+        // only ever called once per swap, so each resource just has to be listed once within the swap
+        fn index_resources(&mut self, proposal: &ProposalView) {
+            for resource in proposal.offered_balances.keys() {
+                Self::push_to_index(&self.by_offered, &self.offered_counts, *resource, proposal.component);
+            }
+
+            let mut requested: Vec<ResourceAddress> = Vec::new();
+            for nft in proposal.nfts_requested.iter() {
+                requested.push(nft.resource_address());
+            }
+            for request in proposal.collections_requested.iter() {
+                requested.push(request.resource);
+            }
+            for resource in proposal.tokens_requested.keys() {
+                requested.push(*resource);
+            }
+            for (index, resource) in requested.iter().enumerate() {
+                // a resource asked for in more than one way is only listed once
+                if !requested[..index].contains(resource) {
+                    Self::push_to_index(&self.by_requested, &self.requested_counts, *resource, proposal.component);
+                }
+            }
+        }
+
+        fn add_to_status(&mut self, status: SwapStatus, component: ComponentAddress) {
+            let position = Self::push_to_index(&self.by_status, &self.status_counts, status, component);
+            self.status_positions.insert(component, position);
+        }
+
+        // moves the last swap under the status into the gap, so the list stays contiguous
+        fn remove_from_status(&mut self, status: SwapStatus, component: ComponentAddress) {
+            let position = *self.status_positions.get(&component).unwrap();
+            let last = *self.status_counts.get(&status).unwrap() - 1;
+
+            if position != last {
+                let moved = *self.by_status.get(&(status.clone(), last)).unwrap();
+                self.by_status.insert((status.clone(), position), moved);
+                self.status_positions.insert(moved, position);
+            }
+            self.by_status.remove(&(status.clone(), last));
+            self.status_counts.insert(status, last);
+        }
+
+        fn push_to_index<K: ScryptoEncode + ScryptoDecode + ScryptoDescribe + Clone>(
+            entries: &KeyValueStore<(K, u64), ComponentAddress>,
+            counts: &KeyValueStore<K, u64>,
+            key: K,
+            component: ComponentAddress,
+        ) -> u64 {
+            let position = counts.get(&key).map(|count| *count).unwrap_or(0);
+            entries.insert((key.clone(), position), component);
+            counts.insert(key, position + 1);
+            position
+        }
+
+        fn read_index<K: ScryptoEncode + ScryptoDecode + ScryptoDescribe + Clone>(
+            entries: &KeyValueStore<(K, u64), ComponentAddress>,
+            counts: &KeyValueStore<K, u64>,
+            key: K,
+            start: u64,
+            limit: u64,
+        ) -> (Vec<ComponentAddress>, u64) {
+            let total = counts.get(&key).map(|count| *count).unwrap_or(0);
+            let end = total.min(start.saturating_add(limit));
+
+            let mut page: Vec<ComponentAddress> = Vec::new();
+            for position in start..end {
+                page.push(*entries.get(&(key.clone(), position)).unwrap());
+            }
+
+            (page, total)
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use scrypto::prelude::*;
+use crate::barter::barter::Barter;
+
+// For circular wants no single swap can satisfy - e.g. A offers Cars wants Phones, B offers Phones wants Laptops,
+// C offers Laptops wants Cars. Each creator's side is routed straight on to the next swap in the ring, all in one transaction.
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct RingSettled {
+    proposals: Vec<ComponentAddress>,
+}
+
+#[blueprint]
+#[events(RingSettled)]
+mod ring {
+
+    enable_package_royalties! {
+        settle_ring => Free;
+    }
+
+    struct RingSettlement {}
+
+    impl RingSettlement {
+        /// `proposals` is the ring in order - each swap's offer has to fill the request of the one after it,
+        /// and the last one's offer fills the first one's request. If any leg doesn't match, nothing moves.
+
+        pub fn settle_ring(proposals: Vec<ComponentAddress>) {
+            assert!(proposals.len() >= 2, "A ring needs at least two swaps");
+            // ring_release and ring_fill are called by name, so anything else with the same two methods could sit in
+            // the ring and take a swap's assets without ever paying its fees or royalties - only Trove swaps can join
+            let barter = BlueprintId::new(&Runtime::package_address(), "Barter");
+            for (index, proposal) in proposals.iter().enumerate() {
+                assert!(!proposals[..index].contains(proposal), "Duplicate swap in ring");
+                assert!(
+                    Global::<Barter>::from(*proposal).blueprint_id() == barter,
+                    "{:?} isn't a Trove swap",
+                    proposal
+                );
+            }
+
+            let mut released: Vec<Vec<Bucket>> = Vec::new();
+            for proposal in proposals.iter() {
+                released.push(Global::<Barter>::from(*proposal).ring_release());
+            }
+
+            for (index, buckets) in released.into_iter().enumerate() {
+                let next = proposals[(index + 1) % proposals.len()];
+                Global::<Barter>::from(next).ring_fill(buckets);
+            }
+
+            Runtime::emit_event(RingSettled { proposals });
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use scrypto::prelude::*;
+use crate::barter::{Escroceipt, SwapStatus};
+
+// Blind auctions for fair launches. Bidders commit to a bid without showing it - the hash of the scrypto-encoded
+// (amount, salt) - and lock a deposit at least that big alongside. Once commits close, bidders reveal their amount and
+// salt, and the highest valid reveal wins the bundle, paying what they bid. Anyone who loses or never reveals takes their
+// deposit back once reveals close. Deposits are public, so bidders who don't want to give their bid away should overpay.
+
+// Bidders get one of these per commitment - it's what they reveal, refund or claim the bundle with.
+#[derive(NonFungibleData, ScryptoSbor, Debug)]
+pub struct SealedBidReceipt {
+    name: String,
+    description: String,
+    key_image_url: Url,
+    auction_component: ComponentAddress,
+    commitment: Hash,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct SealedAuctionCreated {
+    component: ComponentAddress,
+    creator_badge: ResourceAddress,
+    bid_resource: ResourceAddress,
+    commit_ends_at: Instant,
+    reveal_ends_at: Instant,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct BidCommitted {
+    component: ComponentAddress,
+    bid_id: NonFungibleLocalId,
+    deposit: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct BidRevealed {
+    component: ComponentAddress,
+    bid_id: NonFungibleLocalId,
+    amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct SealedAuctionSettled {
+    component: ComponentAddress,
+    winning_bid: Option<NonFungibleLocalId>,
+    amount: Decimal,
+}
+
+#[blueprint]
+#[events(SealedAuctionCreated, BidCommitted, BidRevealed, SealedAuctionSettled)]
+mod sealed_auction {
+
+    enable_package_royalties! {
+        new_sealed_auction => Free;
+        commit_bid => Free;
+        reveal_bid => Free;
+        refund_bid => Free;
+        winner_claim => Free;
+        seller_claim => Free;
+        seller_cancel => Free;
+        get_badge => Free;
+        get_bid_receipt => Free;
+        get_high_bid => Free;
+        get_phases => Free;
+        status => Free;
+    }
+
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [];
+        },
+        methods {
+            seller_claim => restrict_to: [admin];
+            seller_cancel => restrict_to: [admin];
+            commit_bid => PUBLIC;
+            reveal_bid => PUBLIC;
+            refund_bid => PUBLIC;
+            winner_claim => PUBLIC;
+            get_badge => PUBLIC;
+            get_bid_receipt => PUBLIC;
+            get_high_bid => PUBLIC;
+            get_phases => PUBLIC;
+            status => PUBLIC;
+        }
+    }
+
+    struct SealedAuction {
+        auction_component: ComponentAddress,
+
+        // the bundle and the seller's key
+        creator_vaults: HashMap<ResourceAddress, Vault>,
+        a_vault_key: ResourceAddress,
+        a_vault_key_id: NonFungibleLocalId,
+
+        // bidding rules
+        bid_resource: ResourceAddress,
+        // reveals have to fit it, or the winning amount couldn't be taken out of the deposit at settlement
+        bid_divisibility: u8,
+        reserve_price: Decimal,
+        commit_ends_at: Instant,
+        reveal_ends_at: Instant,
+
+        // every commitment and its deposit, keyed by receipt
+        commitments: KeyValueStore<NonFungibleLocalId, Hash>,
+        deposits: KeyValueStore<NonFungibleLocalId, Vault>,
+        revealed: KeyValueStore<NonFungibleLocalId, Decimal>,
+        bid_receipt_manager: ResourceManager,
+        bid_count: u64,
+        // highest reveal so far - ties go to whoever revealed first
+        high_bid: Option<(NonFungibleLocalId, Decimal)>,
+
+        // the winning amount, moved out of the winner's deposit at settlement
+        proceeds: Vault,
+        status: SwapStatus,
+        seller_claimed: bool,
+        winner_claimed: bool,
+    }
+
+    impl SealedAuction {
+        pub fn new_sealed_auction(
+            // Just a random name that users can give their auctions
+            custom_trade_name: String,
+            // Optional to include a variety of fungibles in the bundle
+            a_tokens: Option<Vec<Bucket>>,
+            // Optional to include a variety of non fungibles in the bundle
+            a_nfts: Option<Vec<Bucket>>,
+            // What bids are paid in
+            bid_resource: ResourceAddress,
+            // Revealed bids below this don't count
+            reserve_price: Decimal,
+            // When commits close and reveals open
+            commit_ends_at: Instant,
+            // When reveals close and the auction can be settled
+            reveal_ends_at: Instant,
+        ) -> (Global<SealedAuction>, NonFungibleBucket) {
+            assert!(a_nfts.is_some() || a_tokens.is_some(), "You need to offer something");
+            assert!(bid_resource.is_fungible(), "Bids have to be fungible");
+            assert!(reserve_price > Decimal::zero(), "Reserve price must be positive");
+            assert!(
+                Clock::current_time_is_strictly_before(commit_ends_at, TimePrecision::Minute),
+                "Commit phase would end before it starts"
+            );
+            assert!(
+                commit_ends_at.seconds_since_unix_epoch < reveal_ends_at.seconds_since_unix_epoch,
+                "Reveal phase has to end after the commit phase"
+            );
+
+            let bid_divisibility = match ResourceManager::from(bid_resource).resource_type() {
+                ResourceType::Fungible { divisibility } => divisibility,
+                ResourceType::NonFungible { .. } => panic!("Bids have to be fungible"),
+            };
+
+            let (address_reservation, component_address) =
+                Runtime::allocate_component_address(SealedAuction::blueprint_id());
+
+            // same bundle handling and 50 asset cap as Barter
+            let mut nfts_offered: Vec<NonFungibleGlobalId> = Vec::new();
+            let mut tokens_offered: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            let mut creator_vaults: HashMap<ResourceAddress, Vault> = HashMap::new();
+            for bucket in a_tokens.unwrap_or(Vec::new()).into_iter() {
+                *tokens_offered
+                    .entry(bucket.resource_address())
+                    .or_insert(Decimal::zero()) += bucket.amount();
+                creator_vaults
+                    .entry(bucket.resource_address())
+                    .or_insert_with(|| Vault::new(bucket.resource_address()))
+                    .put(bucket)
+            }
+            for bucket in a_nfts.unwrap_or(Vec::new()).into_iter() {
+                let resource = bucket.resource_address();
+                for nft_id in bucket.as_non_fungible().non_fungible_local_ids().into_iter() {
+                    nfts_offered.push(NonFungibleGlobalId::new(resource, nft_id))
+                }
+                creator_vaults
+                    .entry(resource)
+                    .or_insert_with(|| Vault::new(resource))
+                    .put(bucket)
+            }
+            assert!(
+                nfts_offered.len() + tokens_offered.len() <= 50,
+                "Reached single transaction event limit"
+            );
+
+            let key_custom_label = String::from("TROVE Key: ") + &custom_trade_name;
+
+            let mut tokens_requested: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            tokens_requested.insert(bid_resource, reserve_price);
+
+            let a_key = ResourceBuilder::new_string_non_fungible::<Escroceipt>(OwnerRole::None)
+                .metadata(metadata! {
+                    roles {
+                        metadata_locker => rule!(deny_all);
+                        metadata_locker_updater => rule!(deny_all);
+                        metadata_setter => rule!(deny_all);
+                        metadata_setter_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => key_custom_label, locked;
+                        "description" => "Your Sealed Auction on trove.tools".to_owned(), locked;
+                        "key_image_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                })
+                .mint_roles(mint_roles!(
+                    minter => rule!(deny_all);
+                    minter_updater => rule!(deny_all);
+                ))
+                .burn_roles(burn_roles!(
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                ))
+                .mint_initial_supply(vec![(
+                    "Trove_Creator_Key".try_into().unwrap(),
+                    Escroceipt {
+                        name: "Trove Sealed Auction".to_owned(),
+                        description: "This NFT contains details of your Sealed Auction on Trove".to_owned(),
+                        key_image_url: Url::of("https://trove.tools/multiple.png"),
+                        swap_component: component_address,
+                        nfts_offered,
+                        tokens_offered,
+                        nfts_requested: Vec::new(),
+                        collections_requested: Vec::new(),
+                        tokens_requested,
+                    },
+                )]);
+
+            let bid_receipt_manager = ResourceBuilder::new_ruid_non_fungible::<SealedBidReceipt>(OwnerRole::None)
+                .metadata(metadata! {
+                    roles {
+                        metadata_locker => rule!(deny_all);
+                        metadata_locker_updater => rule!(deny_all);
+                        metadata_setter => rule!(deny_all);
+                        metadata_setter_updater => rule!(deny_all);
+                    },
+                    init {
+                        "name" => "TROVE Sealed Bid Receipt".to_owned(), locked;
+                        "description" => "Proof of your sealed bid on an Auction on trove.tools".to_owned(), locked;
+                        "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                    }
+                })
+                .mint_roles(mint_roles!(
+                    minter => rule!(require(global_caller(component_address)));
+                    minter_updater => rule!(deny_all);
+                ))
+                .burn_roles(burn_roles!(
+                    burner => rule!(require(global_caller(component_address)));
+                    burner_updater => rule!(deny_all);
+                ))
+                .create_with_no_initial_supply();
+
+            let global_key_id = NonFungibleGlobalId::new(
+                a_key.resource_address(),
+                a_key.as_non_fungible().non_fungible_local_id(),
+            );
+
+            Runtime::emit_event(SealedAuctionCreated {
+                component: component_address,
+                creator_badge: a_key.resource_address(),
+                bid_resource,
+                commit_ends_at,
+                reveal_ends_at,
+            });
+
+            let auction_component = Self {
+                auction_component: component_address,
+                creator_vaults,
+                a_vault_key: a_key.resource_address(),
+                a_vault_key_id: a_key.as_non_fungible().non_fungible_local_id(),
+                bid_resource,
+                bid_divisibility,
+                reserve_price,
+                commit_ends_at,
+                reveal_ends_at,
+                commitments: KeyValueStore::new(),
+                deposits: KeyValueStore::new(),
+                revealed: KeyValueStore::new(),
+                bid_receipt_manager,
+                bid_count: 0,
+                high_bid: None,
+                proceeds: Vault::new(bid_resource),
+                status: SwapStatus::Open,
+                seller_claimed: false,
+                winner_claimed: false,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .metadata(metadata! (
+                roles {
+                    metadata_setter => rule!(deny_all);
+                    metadata_setter_updater => rule!(deny_all);
+                    metadata_locker => rule!(deny_all);
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name" => "Trove Sealed Auction".to_owned(), locked;
+                    "description" => "Find your auction on https://trove.tools".to_owned(), locked;
+                    "tags" => vec!["Auction".to_string()], locked;
+                    "icon_url" => Url::of("https://trove.tools/TroveSquare.png"), locked;
+                }
+            ))
+            .roles(roles!(
+                admin => rule!(require(global_key_id));
+            ))
+            .with_address(address_reservation)
+            .globalize();
+
+            (auction_component, a_key)
+        }
+
+        /// Locks a deposit in against a commitment - the hash of the scrypto-encoded (amount, salt) - and hands back
+        /// the receipt. The deposit has to cover whatever gets revealed later.
+
+        pub fn commit_bid(&mut self, commitment: Hash, deposit: Bucket) -> Bucket {
+            assert!(self.status == SwapStatus::Open, "Auction is closed");
+            assert!(
+                Clock::current_time_is_strictly_before(self.commit_ends_at, TimePrecision::Minute),
+                "Commit phase has ended"
+            );
+            assert!(deposit.resource_address() == self.bid_resource, "Wrong bid resource");
+            assert!(deposit.amount() >= self.reserve_price, "Deposit is below the reserve price");
+
+            let receipt = self.bid_receipt_manager.mint_ruid_non_fungible(SealedBidReceipt {
+                name: "Trove Sealed Bid".to_owned(),
+                description: "This NFT lets you reveal and refund your bid, or claim the bundle if you win".to_owned(),
+                key_image_url: Url::of("https://trove.tools/multiple.png"),
+                auction_component: self.auction_component,
+                commitment,
+            });
+            let bid_id = receipt.as_non_fungible().non_fungible_local_id();
+
+            Runtime::emit_event(BidCommitted {
+                component: self.auction_component,
+                bid_id: bid_id.clone(),
+                deposit: deposit.amount(),
+            });
+
+            self.commitments.insert(bid_id.clone(), commitment);
+            self.deposits.insert(bid_id, Vault::with_bucket(deposit));
+            self.bid_count += 1;
+
+            receipt
+        }
+
+        /// Opens a commitment during the reveal phase. Reveals that don't match, fall below the reserve, aren't
+        /// covered by the deposit or have more decimal places than the bid resource allows are refused outright.
+
+        pub fn reveal_bid(&mut self, bid_receipt: Proof, amount: Decimal, salt: String) {
+            let bid_id = self.check_bid_receipt(bid_receipt);
+            assert!(
+                Clock::current_time_is_at_or_after(self.commit_ends_at, TimePrecision::Minute),
+                "Reveal phase hasn't started"
+            );
+            assert!(
+                Clock::current_time_is_strictly_before(self.reveal_ends_at, TimePrecision::Minute),
+                "Reveal phase has ended"
+            );
+            assert!(self.revealed.get(&bid_id).is_none(), "Bid has already been revealed");
+
+            let commitment = *self.commitments.get(&bid_id).expect("No such bid");
+            assert!(
+                hash(scrypto_encode(&(amount, salt)).unwrap()) == commitment,
+                "Amount and salt don't match the commitment"
+            );
+            assert!(amount >= self.reserve_price, "Bid is below the reserve price");
+            assert!(
+                amount.checked_round(self.bid_divisibility, RoundingMode::ToZero) == Some(amount),
+                "Bid has more decimal places than the bid resource allows"
+            );
+            assert!(
+                amount <= self.deposits.get(&bid_id).unwrap().amount(),
+                "Bid is more than the deposit"
+            );
+
+            self.revealed.insert(bid_id.clone(), amount);
+            if self.high_bid.as_ref().map(|(_, high_amount)| amount > *high_amount).unwrap_or(true) {
+                self.high_bid = Some((bid_id.clone(), amount));
+            }
+
+            Runtime::emit_event(BidRevealed {
+                component: self.auction_component,
+                bid_id,
+                amount,
+            });
+        }
+
+        /// Once reveals close, every deposit but the winner's can be taken back - lost and unrevealed alike.
+
+        pub fn refund_bid(&mut self, bid_receipt: Proof) -> Bucket {
+            let bid_id = self.check_bid_receipt(bid_receipt);
+            self.settle();
+            assert!(
+                self.high_bid.as_ref().map(|(high_id, _)| high_id != &bid_id).unwrap_or(true),
+                "The winning bid can't be refunded"
+            );
+
+            let mut deposit = self.deposits.get_mut(&bid_id).expect("No such bid");
+            assert!(!deposit.is_empty(), "Bid has already been refunded");
+            deposit.take_all()
+        }
+
+        /// After reveals close, the winner collects the bundle along with whatever their deposit had over their bid.
+
+        pub fn winner_claim(&mut self, bid_receipt: Proof) -> Vec<Bucket> {
+            let bid_id = self.check_bid_receipt(bid_receipt);
+            self.settle();
+            assert!(
+                self.high_bid.as_ref().map(|(high_id, _)| high_id == &bid_id).unwrap_or(false),
+                "Not the winning bid"
+            );
+            assert!(!self.winner_claimed, "Bundle has already been claimed");
+            self.winner_claimed = true;
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.creator_vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+            buckets.push(self.deposits.get_mut(&bid_id).unwrap().take_all());
+
+            return buckets;
+        }
+
+        /// After reveals close, the seller collects the winning bid - or gets their bundle back if nothing valid was revealed.
+
+        pub fn seller_claim(&mut self) -> Vec<Bucket> {
+            self.settle();
+            assert!(!self.seller_claimed, "Auction proceeds have already been claimed");
+            self.seller_claimed = true;
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            match self.high_bid {
+                Some(_) => buckets.push(self.proceeds.take_all()),
+                None => {
+                    for vault in self.creator_vaults.values_mut() {
+                        buckets.push(vault.take_all())
+                    }
+                }
+            }
+
+            return buckets;
+        }
+
+        /// The seller can only back out before anyone has committed.
+
+        pub fn seller_cancel(&mut self) -> Vec<Bucket> {
+            assert!(self.status == SwapStatus::Open, "Auction is closed");
+            assert!(self.bid_count == 0, "Auction already has bids");
+            self.status = SwapStatus::Cancelled;
+
+            let mut buckets: Vec<Bucket> = Vec::new();
+            for vault in self.creator_vaults.values_mut() {
+                buckets.push(vault.take_all())
+            }
+
+            return buckets;
+        }
+
+        // creator key resource and local id
+        pub fn get_badge(&self) -> (ResourceAddress, NonFungibleLocalId) {
+            (self.a_vault_key, self.a_vault_key_id.clone())
+        }
+
+        pub fn get_bid_receipt(&self) -> ResourceAddress {
+            self.bid_receipt_manager.address()
+        }
+
+        // only moves during the reveal phase
+        pub fn get_high_bid(&self) -> Option<(NonFungibleLocalId, Decimal)> {
+            self.high_bid.clone()
+        }
+
+        // when commits close, and when reveals close
+        pub fn get_phases(&self) -> (Instant, Instant) {
+            (self.commit_ends_at, self.reveal_ends_at)
+        }
+
+        pub fn status(&self) -> SwapStatus {
+            self.status.clone()
+        }
+
+        // closes the auction the first time anyone claims or refunds after reveals close, setting the winning amount aside
+        fn settle(&mut self) {
+            assert!(
+                Clock::current_time_is_at_or_after(self.reveal_ends_at, TimePrecision::Minute),
+                "Reveal phase hasn't ended yet"
+            );
+            if self.status != SwapStatus::Open {
+                assert!(self.status == SwapStatus::Settled, "Auction was cancelled");
+                return;
+            }
+            self.status = SwapStatus::Settled;
+
+            if self.high_bid.is_some() {
+                let (high_id, amount) = self.high_bid.clone().unwrap();
+                let winning_amount = self.deposits.get_mut(&high_id).unwrap().take(amount);
+                self.proceeds.put(winning_amount);
+            }
+
+            Runtime::emit_event(SealedAuctionSettled {
+                component: self.auction_component,
+                winning_bid: self.high_bid.as_ref().map(|(high_id, _)| high_id.clone()),
+                amount: self.high_bid.as_ref().map(|(_, amount)| *amount).unwrap_or(Decimal::zero()),
+            });
+        }
+
+        fn check_bid_receipt(&self, bid_receipt: Proof) -> NonFungibleLocalId {
+            bid_receipt
+                .check(self.bid_receipt_manager.address())
+                .as_non_fungible()
+                .non_fungible_local_id()
+        }
+    }
+}
@@ -2,9 +2,10 @@ use scrypto::prelude::*;
 use scrypto_unit::*;
 use transaction::builder::ManifestBuilder;
 
-// Contains three functions, each with transcations that are either expected to pass or fail.
+// Contains a handful of functions, each with transcations that are either expected to pass or fail.
 // Test 1: test_basic_swap - 
 // ---- account 1 creates swap 
+// ---- account 2 attempts to bind the swap with empty NFT and token deposits || failure
 // ---- account 2 accepts
 // ---- account 2 attempts to cancel swap after its accepted || failure
 // ---- account 1 attempts to cancel swap after its accepted || failure
@@ -24,6 +25,229 @@ use transaction::builder::ManifestBuilder;
 // ---- account 1 cancels swap with badge
 // ---- account 2 attempts to do swap after its been cancelled || failure
 
+// Test 4: test_accept_trade -
+// ---- account 1 creates swap
+// ---- account 2 attempts to accept with only half the requested NFTs || failure
+// ---- account 2 accepts, depositing and claiming in a single call
+// ---- account 1 completes swap, collecting their new assets
+
+// Test 5: test_collection_request -
+// ---- account 1 creates swap offering tokens for any 2 NFTs from account 2's collection
+// ---- account 2 attempts to accept with a single NFT from the collection || failure
+// ---- account 2 accepts with two NFTs that weren't named up front
+
+// Test 6: test_trait_request -
+// ---- account 1 creates a swap requesting 2 Fords, and another requesting 1 Ford
+// ---- account 2 attempts to fill the 2 Ford swap with all their cars, only one is a Ford || failure
+// ---- account 2 fills the 1 Ford swap with all their cars
+
+// Test 7: test_expiry -
+// ---- account 1 creates swap expiring in an hour
+// ---- account 2 attempts to expire swap before its deadline || failure
+// ---- clock moves past the deadline
+// ---- account 2 attempts to accept swap || failure
+// ---- account 2 expires swap
+// ---- account 1 cancels swap, collecting their assets
+
+// Test 8: test_counter_offer -
+// ---- account 1 creates swap requesting two NFTs
+// ---- account 2 proposes a counter offer of only one of them
+// ---- account 2 attempts to accept their own counter offer || failure
+// ---- account 1 accepts the counter offer, collecting the NFT
+// ---- account 2 collects account 1's assets with their counter receipt
+
+// Test 9: test_draft_proposal -
+// ---- account 1 creates an empty draft swap
+// ---- account 1 adds their cars to the offer and requests XRD
+// ---- account 2 attempts to fund the swap before its published || failure
+// ---- account 1 publishes the swap, account 2 reads it back
+// ---- account 1 attempts to add to the request after publishing || failure
+// ---- account 2 funds the swap and claims the cars two at a time
+// ---- account 2 attempts to burn their receipt between pages || failure
+// ---- account 1 collects the XRD a page at a time
+
+// Test 10: test_registry -
+// ---- registry is instantiated with a platform fee
+// ---- account 1 attempts to sign a swap up to the registry without going through it || failure
+// ---- account 1 creates swap through the registry
+// ---- account 2 accepts through a frontend, which gets half the platform fee
+// ---- registry shows the swap as settled, and lists it under the offered resource
+// ---- account 2 attempts to collect the platform fees || failure
+// ---- account 1 collects the platform fee taken from account 2's XRD
+
+// Test 11: test_ring_trade -
+// ---- account 1 offers a car for a phone, account 2 offers that phone for XRD, account 3 offers XRD for the car
+// ---- account 1 attempts to release their swap's assets directly || failure
+// ---- ring is settled in the wrong order || failure
+// ---- ring is settled in the right order
+// ---- account 1 collects the phone
+
+// Test 12: test_multi_party_swap -
+// ---- account 1 sends a car to account 2, account 2 sends a phone to account 3, account 3 sends XRD to account 1
+// ---- account 1 and account 2 deposit
+// ---- account 3 attempts to claim before depositing || failure
+// ---- account 2 pulls their deposit back out and redeposits
+// ---- account 3 deposits, settling the swap
+// ---- account 1 collects the XRD
+
+// Test 13: test_collection_royalty -
+// ---- account 1 offers a painting whose collection pays account 3 a 10% royalty
+// ---- account 2 buys it for 100 XRD
+// ---- account 3 has been paid 10 XRD, account 1 collects the other 90
+
+// Test 14: test_membership_discount -
+// ---- registry is instantiated with half off the fee for holding 1 membership token, and no fee for holding 10
+// ---- account 1 creates swap through the registry
+// ---- account 2 attempts to claim a discount with a proof of XRD || failure
+// ---- account 2 accepts with a proof of 1 membership token, paying half the fee
+// ---- account 1 collects the discounted fee
+
+// Test 15: test_creator_approval -
+// ---- account 1 creates swap that needs their approval
+// ---- account 2 attempts to take the swap in one go || failure
+// ---- account 2 deposits the car, then attempts to claim before approval || failure
+// ---- account 1 rejects the deposit
+// ---- account 2 attempts to burn their receipt before collecting the car || failure
+// ---- account 2 collects the car back with their receipt
+// ---- account 2 deposits again, account 1 approves and account 2 claims the XRD
+
+// Test 16: test_arbiter -
+// ---- account 1 creates swap with account 3 as arbiter and an hour's dispute window
+// ---- account 2 counters with their other car, account 1 attempts to accept it || failure
+// ---- account 2 deposits the car, then attempts to claim during the dispute window || failure
+// ---- account 2 attempts to release the swap themselves || failure
+// ---- account 3 releases the swap
+// ---- account 2 attempts to burn their receipt before claiming || failure
+// ---- account 2 claims the XRD and account 1 the car
+
+// Test 17: test_settlement_delay -
+// ---- account 1 creates two swaps for account 2's cars, each locked for an hour once funded
+// ---- account 2 funds both swaps
+// ---- account 2 attempts to claim during the delay || failure
+// ---- account 1 attempts to pull out during the delay || failure
+// ---- account 1 raises a dispute on the second swap
+// ---- clock moves past the delay, account 2 claims the first swap's XRD
+// ---- account 2 attempts to claim the disputed swap || failure
+// ---- account 2 takes their car back out of the disputed swap
+
+// Test 18: test_english_auction -
+// ---- account 1 auctions a car for XRD, starting at 100 with 10 XRD increments
+// ---- account 2 bids 100
+// ---- account 3 attempts to bid 105 || failure
+// ---- account 3 bids 110, account 2 refunds their bid
+// ---- account 3 attempts to claim the car before the end || failure
+// ---- clock moves past the end, account 3 claims the car and account 1 the 110 XRD
+
+// Test 19: test_dutch_listing -
+// ---- account 1 lists a car for XRD, falling from 1000 to 100 over ten hours
+// ---- clock moves halfway, account 2 attempts to pay 500 || failure
+// ---- account 2 pays 600, gets the car and 50 XRD change
+// ---- account 3 attempts to take the listing again || failure
+// ---- account 1 claims the 550 XRD
+
+// Test 20: test_sealed_auction -
+// ---- account 1 puts a car up for a sealed auction with a 100 XRD reserve
+// ---- account 2 commits to 150 with a 200 deposit, account 3 to 120 and account 4 to 130
+// ---- account 3 attempts to reveal while commits are open || failure
+// ---- commits close, account 2 attempts to reveal with the wrong salt || failure
+// ---- account 2 and account 3 reveal, account 4 never does
+// ---- account 3 attempts a refund before reveals close || failure
+// ---- reveals close, account 3 and account 4 take their deposits back
+// ---- account 2 claims the car and 50 XRD change, account 1 claims the 150 XRD
+
+// Test 21: test_staged_deposit -
+// ---- account 1 creates swap requesting two of account 2's cars
+// ---- account 2 deposits the first car
+// ---- account 2 attempts to claim with only one car in || failure
+// ---- account 2 attempts to deposit the second car without their receipt || failure
+// ---- account 2 deposits the second car with their receipt and claims the XRD
+// ---- account 1 collects both cars
+
+// Test 22: test_partner_claim_key -
+// ---- account 2 sets their account to refuse resources they've not seen before
+// ---- account 1 creates swap for account 2 only, whose partner key is refused and held by the swap
+// ---- account 3 attempts to claim account 2's key || failure
+// ---- account 2 claims their key, and takes the swap with it
+
+// Test 23: test_holder_gate -
+// ---- account 1 offers a car for 500 XRD, only to holders of at least 5 gate tokens
+// ---- account 3, who holds none, attempts to take the swap || failure
+// ---- account 3 is given 1 gate token, attempts to take the swap with it || failure
+// ---- account 2 takes the swap with a proof of 5 gate tokens
+
+// Test 24: test_multiple_partners -
+// ---- account 1 attempts to create swap naming account 2 twice || failure
+// ---- account 1 offers a car for 500 XRD to accounts 2 and 3, who get Trove_Partner_Key_1 and Trove_Partner_Key_2
+// ---- account 1 attempts to take the swap with their creator key || failure
+// ---- account 3 takes the swap, which is recorded as filled by their key
+
+// Test 25: test_swap_status -
+// ---- account 1 offers 1000 XRD for a car, the swap reads as open
+// ---- account 2 deposits the car, the swap reads as partner funded
+// ---- account 2 claims the XRD, the swap reads as settled
+// ---- account 1 attempts to cancel the settled swap || failure
+// ---- account 1 offers the same again and cancels it, the swap reads as cancelled
+
+// Test 26: test_ring_foreign_component -
+// ---- account 1 offers 1000 XRD for account 2's car
+// ---- account 2 puts the car in a component that isn't a Trove swap, but has ring_release and ring_fill
+// ---- ring is settled through that component || failure
+// ---- account 1's XRD is still in the swap
+
+// Test 27: test_sealed_auction_divisibility -
+// ---- account 1 puts a car up for a sealed auction, taking bids in a token with no decimal places
+// ---- account 2 commits to 150.5 with a 200 deposit, account 3 to 120
+// ---- commits close, account 2 attempts to reveal 150.5 || failure
+// ---- account 3 reveals
+
+// Test 28: test_staged_tokens -
+// ---- account 1 offers 1000 XRD for 300 of account 2's tokens and 100 XRD
+// ---- account 2 deposits 100 tokens
+// ---- account 2 attempts to deposit another 250 tokens || failure
+// ---- account 2 deposits the other 200 tokens and the 100 XRD with their receipt, the swap reads as all tokens in
+// ---- account 2 cancels, taking back one asset
+// ---- account 3 attempts to fund the swap before account 2 has withdrawn everything || failure
+// ---- account 2 takes back the rest, account 3 funds the swap
+
+// Test 29: test_dutch_divisibility -
+// ---- account 1 attempts to list a car for a token with no decimal places, with a floor of 100.5 || failure
+// ---- account 1 lists the car for that token, falling from 1000 to 100 over seven hours
+// ---- clock moves an hour, the price reads as 872 rather than 871.43
+// ---- account 2 attempts to pay 871 || failure
+// ---- account 2 pays 900, gets the car and 28 change, account 1 claims the 872
+
+// Test 30: test_partner_key_reclaim -
+// ---- account 2, an allocated account with no owner_keys, refuses anything they've not seen before
+// ---- account 1 creates swap for account 2 only, whose partner key is refused and held by the swap
+// ---- account 2 attempts to claim their key || failure
+// ---- account 3 attempts to reclaim the key for the creator || failure
+// ---- account 1 reclaims the key and hands it to account 2 in a transaction they both sign
+// ---- account 2 takes the swap with it
+
+// Test 31: test_arbiter_filled_by -
+// ---- account 1 creates swap for account 2 only, with account 3 as arbiter
+// ---- account 2 deposits the car with their partner key
+// ---- account 3 releases the swap, which is recorded as filled by account 2's key
+
+// Test 32: test_overlapping_trait_requests -
+// ---- account 1 creates swap requesting 1 Ford and 1 Raptor from account 2's fleet
+// ---- account 2 attempts to fill it with just the Raptor, which fits either request || failure
+// ---- account 2 fills it with the Raptor first and the Mustang second
+// ---- reveals close, account 2 takes their deposit back, account 3 claims the car and account 1 the 120
+
+// mirrors ProposalOptions in the blueprint - the gate, arbiter and referral go in as (resource, amount),
+// (account, dispute_window) and (account, share_bps)
+#[derive(ManifestSbor, Clone, Default)]
+struct ProposalOptions {
+    holder_gate: Option<(ResourceAddress, Decimal)>,
+    valid_from: Option<Instant>,
+    expires_at: Option<Instant>,
+    require_approval: bool,
+    arbiter: Option<(ComponentAddress, i64)>,
+    settlement_delay: Option<i64>,
+    referral: Option<(ComponentAddress, u16)>,
+}
+
 #[test]
 fn test_basic_swap() {
     // Setup the environment
@@ -104,11 +328,15 @@ fn test_basic_swap() {
     let btree1: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(firstrs.1);
     let btree2: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(secondrs.1);
     let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
     let request_global = vec![global_1, global_2];
     let request: Option<Vec<NonFungibleGlobalId>> = Some(request_global);
-    let partner_option = None as Option<ComponentAddress>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
     let partner_badge = None as Option<ManifestProof>;
     let partner_badge2 = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
 
     // NFT set up complete
 
@@ -134,11 +362,14 @@ fn test_basic_swap() {
                 "new_trade_proposal",
                 manifest_args!(
                     "My new trade!", // String name
-                    partner_option,
+                    partner_option.clone(),
                     Some(vec![lookup.bucket("bucket2")]),
                     Some(vec![lookup.bucket("bucket1")]),
                     request.clone(),
-                    none_hashmap_fungibles.clone()
+                    no_collections.clone(),
+                    none_hashmap_fungibles.clone(),
+                    no_registry,
+                    ProposalOptions::default()
                 ),
             )
         })
@@ -166,11 +397,14 @@ let manifest = ManifestBuilder::new()
         "new_trade_proposal",
         manifest_args!(
             "My new trade!", // String name
-            partner_option,
+            partner_option.clone(),
             blank_tokens,
             blank_nfts,
             request,
-            none_hashmap_fungibles
+            no_collections,
+            none_hashmap_fungibles,
+            no_registry,
+            ProposalOptions::default()
         ),
     )
 .call_method(
@@ -200,7 +434,7 @@ let receipty = test_runner.execute_manifest_ignoring_fee(
     vec![NonFungibleGlobalId::from_public_key(&public_key)],
     );
     receipty.expect_commit_success();
-    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Option<NonFungibleLocalId>) =
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
     receipty.expect_commit(true).output(1);
     let localid_badge = output.1.clone();
     let localid_badge2 = localid_badge.clone();
@@ -210,7 +444,31 @@ let receipty = test_runner.execute_manifest_ignoring_fee(
     let btrbadge: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![output.1]);
 
 
-// account 2 sends NFTs requested and receives assets
+// account 2 attempts to bind the swap without depositing anything - once on the NFT side, once on the token side
+// it doesn't even request | expect failure
+
+for method in ["partner_deposit_nfts", "partner_deposit_tokens"] {
+    let manifest = ManifestBuilder::new()
+    .call_method(
+        component,
+        method,
+        manifest_args!(Vec::<ManifestBucket>::new(), partner_badge, no_receipt, no_referral)
+    )
+    .call_method(
+        account_component2,
+        "deposit_batch",
+        manifest_args!(ManifestExpression::EntireWorktop),
+    )
+    .build();
+    let receipt_empty_deposit = test_runner.execute_manifest_ignoring_fee(
+    manifest,
+    vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_empty_deposit.expect_commit_failure();
+}
+
+
+// account 2 sends NFTs requested, keeping the deposit receipt
 
 let manifest = ManifestBuilder::new()
 
@@ -225,10 +483,70 @@ let manifest = ManifestBuilder::new()
     builder.call_method(
         component,
         "partner_deposit_nfts",
-        manifest_args!(vec![lookup.bucket("bucket1")], partner_badge)
+        manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
     )
 })
-.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge2))
+.call_method(
+    account_component2,
+    "deposit_batch",
+    manifest_args!(ManifestExpression::EntireWorktop),
+)
+.build();
+let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+manifest,
+vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+);
+receipt_b_deposit.expect_commit_success();
+
+// get the deposit receipt account 2 was given
+let manifest = ManifestBuilder::new()
+.call_method(component, "get_deposit_receipt", manifest_args!()).build();
+let receipty = test_runner.execute_manifest_ignoring_fee(
+    manifest,
+    vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipty.expect_commit_success();
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+    receipty.expect_commit(true).output(1);
+    let receipt_resource = deposit_receipt.0;
+    let receipt_local = deposit_receipt.1.unwrap();
+
+
+// account 1 attempts to snipe the creator's assets with their own receipt proof | expect failure
+
+let manifest = ManifestBuilder::new()
+.call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+    receipt_resource,
+    vec![receipt_local.clone()]
+))
+.pop_from_auth_zone("receipt_proof")
+.with_name_lookup(|builder, lookup| {
+    builder.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge2, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+})
+.call_method(
+    account_component,
+    "deposit_batch",
+    manifest_args!(ManifestExpression::EntireWorktop),
+)
+.build();
+let receipt_snipe = test_runner.execute_manifest_ignoring_fee(
+manifest,
+vec![NonFungibleGlobalId::from_public_key(&public_key)],
+);
+receipt_snipe.expect_commit_failure();
+
+
+// account 2 claims the creator's assets with their receipt
+
+let manifest = ManifestBuilder::new()
+.call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+    receipt_resource,
+    vec![receipt_local.clone()]
+))
+.pop_from_auth_zone("receipt_proof")
+.with_name_lookup(|builder, lookup| {
+    builder.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge2, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+})
 .call_method(
     account_component2,
     "deposit_batch",
@@ -299,7 +617,7 @@ let manifest = ManifestBuilder::new()
 .call_method(
         component,
         "creator_claims_partner_assets",
-        manifest_args!(),
+        manifest_args!(None as Option<ManifestProof>),
     )
     .pop_from_auth_zone("new_proof")
 
@@ -401,6 +719,9 @@ fn test_partner_badge_basic_swap() {
     let btree2: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(secondrs.1.clone());
     let btree3: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(secondrs.1.clone());
     let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
     let request_global = vec![global_1, global_2];
     let request: Option<Vec<NonFungibleGlobalId>> = Some(request_global);
 
@@ -428,11 +749,14 @@ fn test_partner_badge_basic_swap() {
                 "new_trade_proposal",
                 manifest_args!(
                     "My new trade!", // String name
-                    Some(account_component2),
+                    Some(vec![account_component2]),
                     Some(vec![lookup.bucket("bucket2")]),
                     Some(vec![lookup.bucket("bucket1")]),
                     request,
-                    none_hashmap_fungibles
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
                 ),
             )
         })
@@ -462,19 +786,31 @@ let receipty = test_runner.execute_manifest_ignoring_fee(
     vec![NonFungibleGlobalId::from_public_key(&public_key)],
     );
     receipty.expect_commit_success();
-    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Option<NonFungibleLocalId>) =
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
     receipty.expect_commit(true).output(1);
     let localid_badge = output.1.clone();
     let localid_badge2 = localid_badge.clone();
     let globalid_badge: NonFungibleGlobalId = output.2.clone();
     let partner_badge = output.3.unwrap();
-    let partner_badge_local = output.4.unwrap();
+    let partner_badge_local = output.4[0].clone();
     println!("partner badge {:?}\n", partner_badge);
     println!("partner local badge {:?}\n", partner_badge_local);
     let localtry = globalid_badge.local_id();
     let rebadge = globalid_badge.resource_address();
     let btrbadge: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![output.1]);
 
+// get deposit receipt address
+let manifest = ManifestBuilder::new()
+.call_method(component, "get_deposit_receipt", manifest_args!()).build();
+let receipty = test_runner.execute_manifest_ignoring_fee(
+    manifest,
+    vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipty.expect_commit_success();
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+    receipty.expect_commit(true).output(1);
+    let receipt_resource = deposit_receipt.0;
+
 // account 2 does not use badge to send and receive assets || expect failure
 
 let manifest = ManifestBuilder::new()
@@ -523,9 +859,11 @@ let manifest = ManifestBuilder::new()
     builder.call_method(
         component,
         "partner_deposit_nfts",
-        manifest_args!(vec![lookup.bucket("bucket1")], Some(lookup.proof("partner_proof")))
+        manifest_args!(vec![lookup.bucket("bucket1")], Some(lookup.proof("partner_proof")), None as Option<ManifestProof>, no_referral)
     )
 })
+.take_all_from_worktop(receipt_resource, "receipt")
+.create_proof_from_bucket_of_all("receipt", "receipt_proof")
 .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
     partner_badge.clone(),
     vec![partner_badge_local.clone()]
@@ -533,7 +871,7 @@ let manifest = ManifestBuilder::new()
 .pop_from_auth_zone("partner_proof2")
 
 .with_name_lookup(|builder, lookup| {
-    builder.call_method(component, "partner_claims_creator_assets", manifest_args!(Some(lookup.proof("partner_proof2"))))
+    builder.call_method(component, "partner_claims_creator_assets", manifest_args!(Some(lookup.proof("partner_proof2")), lookup.proof("receipt_proof"), None as Option<ManifestProof>))
 })
 .call_method(
     account_component2,
@@ -611,7 +949,7 @@ let manifest = ManifestBuilder::new()
 .call_method(
         component,
         "creator_claims_partner_assets",
-        manifest_args!(),
+        manifest_args!(None as Option<ManifestProof>),
     )
     .pop_from_auth_zone("new_proof")
 
@@ -712,11 +1050,14 @@ fn cancel_swap_test () {
   let btree1: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(firstrs.1);
   let btree2: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(secondrs.1);
   let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+  let no_registry = None as Option<ComponentAddress>;
+  let no_referral = None as Option<(ComponentAddress, u16)>;
+  let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
   let request_global = vec![global_1, global_2];
   let request: Option<Vec<NonFungibleGlobalId>> = Some(request_global);
-  let partner_option = None as Option<ComponentAddress>;
+  let partner_option = None as Option<Vec<ComponentAddress>>;
   let partner_badge = None as Option<ManifestProof>;
-  let partner_badge2 = None as Option<ManifestProof>;
+  let no_receipt = None as Option<ManifestProof>;
 
   // NFT set up complete
 
@@ -741,11 +1082,14 @@ fn cancel_swap_test () {
               "new_trade_proposal",
               manifest_args!(
                   "My new trade!", // String name
-                  partner_option,
+                  partner_option.clone(),
                   Some(vec![lookup.bucket("bucket2")]),
                   Some(vec![lookup.bucket("bucket1")]),
                   request,
-                  none_hashmap_fungibles
+                  no_collections,
+                  none_hashmap_fungibles,
+                  no_registry,
+                  ProposalOptions::default()
               ),
           )
       })
@@ -787,7 +1131,7 @@ let receipty = test_runner.execute_manifest_ignoring_fee(
   );
   receipty.expect_commit_success();
 
-  let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Option<NonFungibleLocalId>) =
+  let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
   receipty.expect_commit(true).output(1);
   let globalid_badge: NonFungibleGlobalId = output.2.clone();
   let localtry = globalid_badge.local_id();
@@ -823,10 +1167,9 @@ let manifest = ManifestBuilder::new()
   builder.call_method(
       component,
       "partner_deposit_nfts",
-      manifest_args!(vec![lookup.bucket("bucket1")], partner_badge)
+      manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
   )
 })
-.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge2))
 .call_method(
   account_component2,
   "deposit_batch",
@@ -841,3 +1184,6102 @@ receipt_b_accept.expect_commit_failure();
 
 
 }
+
+#[test]
+fn test_accept_trade() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt.expect_commit_success();
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) = receipt.expect_commit(true).output(1);
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let global_1 = NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone());
+    let global_2 = NonFungibleGlobalId::new(secondrs.0, secondrs.1[1].clone());
+    let locallist = firstrs.1.clone();
+    let btree1: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(firstrs.1);
+    let btree_half: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let btree2: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(secondrs.1.clone());
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let request: Option<Vec<NonFungibleGlobalId>> = Some(vec![global_1, global_2]);
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 creates swap, no partner
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(firstrs.0, locallist),
+        )
+        .call_method(account_component, "withdraw", manifest_args!(
+            XRD,
+            dec!(1000)
+        ))
+        .take_non_fungibles_from_worktop(firstrs.0, btree1, "bucket1")
+        .take_from_worktop(XRD, dec!(1000), "bucket2")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket2")]),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    request,
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!()).build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipty.expect_commit_success();
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let globalid_badge: NonFungibleGlobalId = output.2.clone();
+    let localtry = globalid_badge.local_id();
+    let rebadge = globalid_badge.resource_address();
+
+    // account 2 only sends one of the two requested NFTs | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_half, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens.clone(), partner_badge, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_failure();
+
+    // account 2 accepts the swap in a single call
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, secondrs.1.clone()),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree2, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens, partner_badge, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+
+    // account 1 completes the swap, claiming their requested assets
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry]
+        ))
+        .call_method(
+            component,
+            "creator_claims_partner_assets",
+            manifest_args!(None as Option<ManifestProof>),
+        )
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let a_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    a_accept.expect_commit_success();
+}
+
+#[test]
+fn test_collection_request() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[2].clone()]);
+    let btree_two: BTreeSet<NonFungibleLocalId> =
+        FromIterator::from_iter(vec![secondrs.1[2].clone(), secondrs.1[3].clone()]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_nft_request = None as Option<Vec<NonFungibleGlobalId>>;
+    let any_two_cars: Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>> = Some(vec![(secondrs.0, 2, Vec::new())]);
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 offers tokens for any 2 cars
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(
+            XRD,
+            dec!(1000)
+        ))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    no_nft_request,
+                    any_two_cars,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    // account 2 only sends one car | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[2].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens.clone(), partner_badge, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_failure();
+
+    // account 2 sends any two cars
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[2].clone(), secondrs.1[3].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_two, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens, partner_badge, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+}
+
+#[test]
+fn test_trait_request() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+
+
+
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_nft_request = None as Option<Vec<NonFungibleGlobalId>>;
+    // field 1 on the bootstrap Car is the manufacturer
+    let ford = vec![(1u32, "Ford".to_string())];
+    let two_fords: Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>> = Some(vec![(secondrs.0, 2, ford.clone())]);
+    let one_ford: Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>> = Some(vec![(secondrs.0, 1, ford)]);
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 creates both swaps
+
+    let mut components: Vec<ComponentAddress> = Vec::new();
+    for request in vec![two_fords, one_ford] {
+        let manifest = ManifestBuilder::new()
+            .call_method(account_component, "withdraw", manifest_args!(
+                XRD,
+                dec!(100)
+            ))
+            .take_from_worktop(XRD, dec!(100), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_function(
+                    package_address,
+                    "Barter",
+                    "new_trade_proposal",
+                    manifest_args!(
+                        "My new trade!", // String name
+                        partner_option.clone(),
+                        Some(vec![lookup.bucket("bucket1")]),
+                        no_nfts.clone(),
+                        no_nft_request.clone(),
+                        request,
+                        none_hashmap_fungibles.clone(),
+                        no_registry,
+                        ProposalOptions::default()
+                    ),
+                )
+            })
+            .call_method(
+                account_component,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&public_key)],
+        );
+        receipt_new_trade_proposal.expect_commit_success();
+        components.push(receipt_new_trade_proposal.expect_commit(true).new_component_addresses()[0]);
+    }
+
+    // account 2 tries to fill the 2 Ford swap - only has the one Raptor | expect failure
+    // then fills the 1 Ford swap
+
+    for (component, should_pass) in vec![(components[0], false), (components[1], true)] {
+        let all_cars: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(secondrs.1.clone());
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                account_component2,
+                "withdraw_non_fungibles",
+                manifest_args!(secondrs.0, secondrs.1.clone()),
+            )
+            .take_non_fungibles_from_worktop(secondrs.0, all_cars, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    component,
+                    "accept_trade",
+                    manifest_args!(vec![lookup.bucket("bucket1")], no_tokens.clone(), partner_badge, no_referral, None as Option<ManifestProof>)
+                )
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+        );
+        if should_pass {
+            receipt_b_accept.expect_commit_success();
+        } else {
+            receipt_b_accept.expect_commit_failure();
+        }
+    }
+}
+
+#[test]
+fn test_expiry() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    let now = test_runner.get_current_time(TimePrecision::Minute);
+    let expires_at: Option<Instant> = now.add_hours(1);
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let request_tokens: Option<HashMap<ResourceAddress, Decimal>> = Some(HashMap::from([(XRD, dec!(10))]));
+    let no_nft_request = None as Option<Vec<NonFungibleGlobalId>>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_nft_buckets: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // account 1 creates swap expiring in an hour
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(
+            XRD,
+            dec!(100)
+        ))
+        .take_from_worktop(XRD, dec!(100), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    no_nft_request,
+                    no_collections,
+                    request_tokens,
+                    no_registry,
+                    ProposalOptions {
+                        expires_at,
+                        ..Default::default()
+                    }
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!()).build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipty.expect_commit_success();
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let globalid_badge: NonFungibleGlobalId = output.2.clone();
+
+    // account 2 attempts to expire the swap early | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "expire", manifest_args!())
+        .build();
+    let receipt_early_expire = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_early_expire.expect_commit_failure();
+
+    // move the clock past the deadline
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(1),
+        (now.seconds_since_unix_epoch + 2 * 60 * 60) * 1000,
+    );
+
+    // account 2 attempts to take the swap after its expired | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "withdraw", manifest_args!(
+            XRD,
+            dec!(10)
+        ))
+        .take_from_worktop(XRD, dec!(10), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(no_nft_buckets, vec![lookup.bucket("bucket1")], partner_badge, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_failure();
+
+    // account 2 expires the swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "expire", manifest_args!())
+        .build();
+    let receipt_expire = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_expire.expect_commit_success();
+
+    // account 1 takes their assets back
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            globalid_badge.resource_address(),
+            vec![globalid_badge.local_id().clone()]
+        ))
+        .call_method(component, "creator_cancel", manifest_args!())
+        .call_method(account_component, "deposit_batch", manifest_args!(ManifestExpression::EntireWorktop))
+        .build();
+    let receipt_cancel = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_cancel.expect_commit_success();
+}
+
+#[test]
+fn test_counter_offer() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let global_1 = NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone());
+    let global_2 = NonFungibleGlobalId::new(secondrs.0, secondrs.1[1].clone());
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let request: Option<Vec<NonFungibleGlobalId>> = Some(vec![global_1, global_2]);
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 creates swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(
+            XRD,
+            dec!(1000)
+        ))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request,
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!()).build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipty.expect_commit_success();
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let globalid_badge: NonFungibleGlobalId = output.2.clone();
+
+    // account 2 counters with only one of the requested NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "propose_counter_offer",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens, partner_badge, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_counter = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_counter.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .call_method(component, "get_counter_offers", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipty.expect_commit_success();
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let counters: Vec<NonFungibleLocalId> = receipty.expect_commit(true).output(2);
+    let counter_id = counters[0].clone();
+
+    // account 2 tries to accept their own counter offer | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "creator_accept_counter", manifest_args!(counter_id.clone()))
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_bad_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_bad_accept.expect_commit_failure();
+
+    // account 1 accepts the counter offer
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            globalid_badge.resource_address(),
+            vec![globalid_badge.local_id().clone()]
+        ))
+        .call_method(component, "creator_accept_counter", manifest_args!(counter_id.clone()))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_accept.expect_commit_success();
+
+    // account 2 collects account 1's assets
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            deposit_receipt.0,
+            vec![counter_id]
+        ))
+        .pop_from_auth_zone("counter_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "partner_claim_counter", manifest_args!(lookup.proof("counter_proof")))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim.expect_commit_success();
+}
+
+#[test]
+fn test_draft_proposal() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree1: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(firstrs.1.clone());
+    let mut xrd_request: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    xrd_request.insert(XRD, dec!(100));
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let partner_badge = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 creates an empty draft
+
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            package_address,
+            "Barter",
+            "new_draft",
+            manifest_args!("My big trade!", partner_option.clone(), no_registry,
+                    ProposalOptions::default()
+                ),
+        )
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_draft = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_draft.expect_commit_success();
+
+    let component = receipt_new_draft.expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let localtry = output.1;
+
+    // account 1 fills in the offer and the request
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry.clone()]
+        ))
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(firstrs.0, firstrs.1.clone()),
+        )
+        .take_non_fungibles_from_worktop(firstrs.0, btree1, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "add_offer_nfts", manifest_args!(vec![lookup.bucket("bucket1")]))
+        })
+        .call_method(component, "add_request_tokens", manifest_args!(xrd_request))
+        .build();
+    let receipt_fill = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_fill.expect_commit_success();
+
+    // account 2 tries to fund the draft | expect failure
+
+    let fund_manifest = || {
+        ManifestBuilder::new()
+            .call_method(account_component2, "withdraw", manifest_args!(XRD, dec!(100)))
+            .take_from_worktop(XRD, dec!(100), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    component,
+                    "partner_deposit_tokens",
+                    manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+                )
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        fund_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_failure();
+
+    // account 1 publishes
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry.clone()]
+        ))
+        .call_method(component, "publish", manifest_args!())
+        .build();
+    let receipt_publish = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_publish.expect_commit_success();
+
+    // anyone can read the proposal back
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_proposal", manifest_args!())
+        .build();
+    let receipt_view = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let view: ProposalView = receipt_view.expect_commit(true).output(1);
+    let mut expected_offer: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    expected_offer.insert(firstrs.0, Decimal::from(firstrs.1.len()));
+    let mut expected_request: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    expected_request.insert(XRD, dec!(100));
+    assert_eq!(view.status, SwapStatus::Open);
+    assert_eq!(view.offered_balances, expected_offer);
+    assert_eq!(view.tokens_requested, expected_request);
+    assert!(view.partner_deposit.is_none());
+
+    // account 1 tries to change the request once its live | expect failure
+
+    let mut more_xrd: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    more_xrd.insert(XRD, dec!(1));
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry.clone()]
+        ))
+        .call_method(component, "add_request_tokens", manifest_args!(more_xrd))
+        .build();
+    let receipt_late_add = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_late_add.expect_commit_failure();
+
+    // account 2 funds the swap
+
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        fund_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let receipt_resource = deposit_receipt.0;
+    let receipt_local = deposit_receipt.1.unwrap();
+
+    // account 2 claims the cars two at a time
+
+    for page in 0..2 {
+        let manifest = ManifestBuilder::new()
+            .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+                receipt_resource,
+                vec![receipt_local.clone()]
+            ))
+            .pop_from_auth_zone("receipt_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(component, "partner_claim_page", manifest_args!(partner_badge, lookup.proof("receipt_proof"), 2u32, None as Option<ManifestProof>))
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        let receipt_page = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+        );
+        receipt_page.expect_commit_success();
+
+        // account 2 tries to burn the receipt the second page is still waiting on || failure
+
+        if page == 0 {
+            let manifest = ManifestBuilder::new()
+                .call_method(
+                    account_component2,
+                    "withdraw_non_fungibles",
+                    manifest_args!(receipt_resource, vec![receipt_local.clone()]),
+                )
+                .take_all_from_worktop(receipt_resource, "receipt")
+                .with_name_lookup(|builder, lookup| {
+                    builder.call_method(component, "burn_deposit_receipt", manifest_args!(lookup.bucket("receipt")))
+                })
+                .build();
+            let receipt_burn = test_runner.execute_manifest_ignoring_fee(
+                manifest,
+                vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+            );
+            receipt_burn.expect_commit_failure();
+        }
+    }
+
+    // account 1 collects the XRD
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry]
+        ))
+        .call_method(component, "creator_claim_page", manifest_args!(10u32, None as Option<ManifestProof>))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_a_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_a_claim.expect_commit_success();
+}
+
+// mirrors SwapStatus in the blueprint, for reading getter outputs and passing to them
+#[derive(ScryptoSbor, ManifestSbor, PartialEq, Eq, Debug)]
+enum SwapStatus {
+    Draft,
+    Open,
+    PartnerFunded,
+    PendingApproval,
+    Approved,
+    Settled,
+    Cancelled,
+    Expired,
+}
+
+// mirrors ProposalView in the blueprint - not every field is checked, so some are only here to decode
+#[derive(ScryptoSbor, Debug)]
+#[allow(dead_code)]
+struct ProposalView {
+    component: ComponentAddress,
+    status: SwapStatus,
+    created_at: Instant,
+    valid_from: Option<Instant>,
+    expires_at: Option<Instant>,
+    require_approval: bool,
+    arbiter: Option<ComponentAddress>,
+    settlement_delay: Option<i64>,
+    funded_at: Option<Instant>,
+    disputed: bool,
+    creator_badge: NonFungibleGlobalId,
+    nfts_offered: Vec<NonFungibleGlobalId>,
+    offered_balances: HashMap<ResourceAddress, Decimal>,
+    nfts_requested: Vec<NonFungibleGlobalId>,
+    collections_requested: Vec<(ResourceAddress, u64, Vec<(u32, String)>)>,
+    tokens_requested: HashMap<ResourceAddress, Decimal>,
+    partner_badge: Option<ResourceAddress>,
+    partner_keys: Vec<NonFungibleLocalId>,
+    holder_gate: Option<(ResourceAddress, Decimal)>,
+    filled_by: Option<NonFungibleLocalId>,
+    // (receipt, NFTs in, tokens in, NFTs received so far, tokens received so far, partner key it was made with)
+    partner_deposit: Option<(
+        NonFungibleLocalId,
+        bool,
+        bool,
+        Vec<NonFungibleGlobalId>,
+        HashMap<ResourceAddress, Decimal>,
+        Option<NonFungibleLocalId>,
+    )>,
+    partner_balances: HashMap<ResourceAddress, Decimal>,
+}
+
+#[test]
+fn test_registry() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Create a frontend account
+    let (_public_key3, _private_key3, frontend_account) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // 1% fee, at least 1 and at most 5 on XRD legs, paid to account 1 - frontends can be given up to half of it
+    let fee_config: Option<(u16, ResourceAddress, Decimal, Decimal, ComponentAddress, u16, Option<ResourceAddress>, Vec<(Decimal, u16)>)> =
+        Some((100, XRD, dec!(1), dec!(5), account_component, 5000, None, Vec::new()));
+    let frontend_referral: Option<(ComponentAddress, u16)> = Some((frontend_account, 5000));
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "TroveRegistry", "instantiate_registry", manifest_args!(fee_config.clone()))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let registry = receipt.expect_commit(true).new_component_addresses()[0];
+    let owner_badge = receipt.expect_commit(true).new_resource_addresses()[0];
+
+    // account 1 tries to point a swap at the registry directly, fee config and all | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    Some((registry, fee_config.clone())),
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_direct = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_direct.expect_commit_failure();
+
+    // account 1 creates swap through the registry
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                registry,
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    // account 2 accepts through the frontend
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens, partner_badge, frontend_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let frontend_before = test_runner.get_component_balance(frontend_account, XRD);
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(frontend_account, XRD), frontend_before + dec!("2.5"));
+
+    // registry has caught up
+
+    let manifest = ManifestBuilder::new()
+        .call_method(registry, "get_swap_status", manifest_args!(component))
+        .call_method(registry, "get_by_offered", manifest_args!(XRD, 0u64, 10u64))
+        .call_method(registry, "get_by_status", manifest_args!(SwapStatus::Settled, 0u64, 10u64))
+        .call_method(registry, "get_by_status", manifest_args!(SwapStatus::Open, 0u64, 10u64))
+        .build();
+    let receipt_lookup = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let status: Option<SwapStatus> = receipt_lookup.expect_commit(true).output(1);
+    let (offering_xrd, _): (Vec<ComponentAddress>, u64) = receipt_lookup.expect_commit(true).output(2);
+    let (settled, _): (Vec<ComponentAddress>, u64) = receipt_lookup.expect_commit(true).output(3);
+    let (open, _): (Vec<ComponentAddress>, u64) = receipt_lookup.expect_commit(true).output(4);
+    assert_eq!(status, Some(SwapStatus::Settled));
+    assert!(offering_xrd.contains(&component));
+    assert!(settled.contains(&component));
+    assert!(!open.contains(&component));
+
+    // account 2 attempts to collect the fees || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(registry, "collect_fees", manifest_args!(vec![component]))
+        .build();
+    let receipt_collect = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_collect.expect_commit_failure();
+
+    // account 1 sweeps what's left of the capped fee on account 2's 1000 XRD
+
+    let balance_before = test_runner.get_component_balance(account_component, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_amount", manifest_args!(owner_badge, dec!(1)))
+        .call_method(registry, "collect_fees", manifest_args!(vec![component]))
+        .build();
+    let receipt_collect = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_collect.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, XRD), balance_before + dec!("2.5"));
+}
+
+#[test]
+fn test_ring_trade() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 gets the NFTs, and passes a phone on to account 2
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .call_method(component, "local_ids_2", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let cars: (ResourceAddress, Vec<NonFungibleLocalId>) = receipt.expect_commit(true).output(1);
+    let phones: (ResourceAddress, Vec<NonFungibleLocalId>) = receipt.expect_commit(true).output(2);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(phones.0, vec![phones.1[0].clone()]),
+        )
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![
+            NonFungibleGlobalId::from_public_key(&public_key),
+            NonFungibleGlobalId::from_public_key(&public_key2),
+        ],
+    );
+    receipt.expect_commit_success();
+
+    let car = NonFungibleGlobalId::new(cars.0, cars.1[0].clone());
+    let phone = NonFungibleGlobalId::new(phones.0, phones.1[0].clone());
+    let btree_car: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![cars.1[0].clone()]);
+    let btree_phone: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![phones.1[0].clone()]);
+    let mut xrd_request: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    xrd_request.insert(XRD, dec!(100));
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let no_nft_request = None as Option<Vec<NonFungibleGlobalId>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+
+    // account 1 offers the car for the phone
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(cars.0, vec![cars.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(cars.0, btree_car, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Car for a phone", // String name
+                    partner_option.clone(),
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    Some(vec![phone.clone()]),
+                    no_collections.clone(),
+                    none_hashmap_fungibles.clone(),
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let swap_1 = receipt.expect_commit(true).new_component_addresses()[0];
+
+    // account 2 offers the phone for XRD
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(phones.0, vec![phones.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(phones.0, btree_phone, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Phone for XRD", // String name
+                    partner_option.clone(),
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nft_request.clone(),
+                    no_collections.clone(),
+                    Some(xrd_request.clone()),
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let swap_2 = receipt.expect_commit(true).new_component_addresses()[0];
+
+    // account 3 offers XRD for the car
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component3, "withdraw", manifest_args!(XRD, dec!(100)))
+        .take_from_worktop(XRD, dec!(100), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "XRD for a car", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    Some(vec![car.clone()]),
+                    no_collections.clone(),
+                    none_hashmap_fungibles.clone(),
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component3,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    let swap_3 = receipt.expect_commit(true).new_component_addresses()[0];
+
+    // account 1 tries to pull the car straight out | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(swap_1, "ring_release", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_release = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_release.expect_commit_failure();
+
+    // the car doesn't fill swap 2's request for XRD | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "RingSettlement", "settle_ring", manifest_args!(vec![swap_1, swap_2, swap_3]))
+        .build();
+    let receipt_ring = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_ring.expect_commit_failure();
+
+    // car goes to swap 3, XRD to swap 2, phone to swap 1
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "RingSettlement", "settle_ring", manifest_args!(vec![swap_1, swap_3, swap_2]))
+        .build();
+    let receipt_ring = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_ring.expect_commit_success();
+
+    // account 1 collects the phone
+
+    let manifest = ManifestBuilder::new()
+        .call_method(swap_1, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            output.0,
+            vec![output.1]
+        ))
+        .call_method(swap_1, "creator_claims_partner_assets", manifest_args!(None as Option<ManifestProof>))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_a_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_a_claim.expect_commit_success();
+}
+
+#[test]
+fn test_multi_party_swap() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 gets the NFTs, and passes a phone on to account 2
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .call_method(component, "local_ids_2", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let cars: (ResourceAddress, Vec<NonFungibleLocalId>) = receipt.expect_commit(true).output(1);
+    let phones: (ResourceAddress, Vec<NonFungibleLocalId>) = receipt.expect_commit(true).output(2);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(phones.0, vec![phones.1[0].clone()]),
+        )
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![
+            NonFungibleGlobalId::from_public_key(&public_key),
+            NonFungibleGlobalId::from_public_key(&public_key2),
+        ],
+    );
+    receipt.expect_commit_success();
+
+    let car = NonFungibleGlobalId::new(cars.0, cars.1[0].clone());
+    let phone = NonFungibleGlobalId::new(phones.0, phones.1[0].clone());
+    let btree_car: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![cars.1[0].clone()]);
+    let btree_phone: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![phones.1[0].clone()]);
+    let no_tokens: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    let mut xrd: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    xrd.insert(XRD, dec!(100));
+    let legs: Vec<(u32, u32, Vec<NonFungibleGlobalId>, HashMap<ResourceAddress, Decimal>)> = vec![
+        (0, 1, vec![car], no_tokens.clone()),
+        (1, 2, vec![phone], no_tokens),
+        (2, 0, Vec::new(), xrd),
+    ];
+
+    // NFT set up complete
+
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            package_address,
+            "MultiBarter",
+            "new_multi_swap",
+            manifest_args!(
+                "Three way trade",
+                vec![account_component, account_component2, account_component3],
+                legs
+            ),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt.expect_commit_success();
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let key_resource = receipt.expect_commit(true).new_resource_addresses()[0];
+    let key_1 = NonFungibleLocalId::string("Trove_Participant_Key_1").unwrap();
+    let key_2 = NonFungibleLocalId::string("Trove_Participant_Key_2").unwrap();
+    let key_3 = NonFungibleLocalId::string("Trove_Participant_Key_3").unwrap();
+
+    // account 1 deposits the car
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            key_resource,
+            vec![key_1.clone()]
+        ))
+        .pop_from_auth_zone("key_proof")
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(cars.0, vec![cars.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(cars.0, btree_car, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "participant_deposit",
+                manifest_args!(vec![lookup.bucket("bucket1")], lookup.proof("key_proof"))
+            )
+        })
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt.expect_commit_success();
+
+    // account 2 deposits the phone, then changes their mind and takes it back, then deposits again
+
+    let deposit_phone = || {
+        ManifestBuilder::new()
+            .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+                key_resource,
+                vec![key_2.clone()]
+            ))
+            .pop_from_auth_zone("key_proof")
+            .call_method(
+                account_component2,
+                "withdraw_non_fungibles",
+                manifest_args!(phones.0, vec![phones.1[0].clone()]),
+            )
+            .take_non_fungibles_from_worktop(phones.0, btree_phone.clone(), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    component,
+                    "participant_deposit",
+                    manifest_args!(vec![lookup.bucket("bucket1")], lookup.proof("key_proof"))
+                )
+            })
+            .build()
+    };
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        deposit_phone(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt.expect_commit_success();
+
+    // account 3 tries to claim before they've deposited | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component3, "create_proof_of_non_fungibles", manifest_args!(
+            key_resource,
+            vec![key_3.clone()]
+        ))
+        .pop_from_auth_zone("key_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "participant_claim", manifest_args!(lookup.proof("key_proof")))
+        })
+        .call_method(
+            account_component3,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt.expect_commit_failure();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            key_resource,
+            vec![key_2.clone()]
+        ))
+        .pop_from_auth_zone("key_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "participant_cancel", manifest_args!(lookup.proof("key_proof")))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt.expect_commit_success();
+
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        deposit_phone(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt.expect_commit_success();
+
+    // account 3 deposits the XRD, settling the swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component3, "create_proof_of_non_fungibles", manifest_args!(
+            key_resource,
+            vec![key_3]
+        ))
+        .pop_from_auth_zone("key_proof")
+        .call_method(account_component3, "withdraw", manifest_args!(XRD, dec!(100)))
+        .take_from_worktop(XRD, dec!(100), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "participant_deposit",
+                manifest_args!(vec![lookup.bucket("bucket1")], lookup.proof("key_proof"))
+            )
+        })
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt.expect_commit_success();
+
+    // account 1 collects the XRD
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            key_resource,
+            vec![key_1]
+        ))
+        .pop_from_auth_zone("key_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "participant_claim", manifest_args!(lookup.proof("key_proof")))
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt.expect_commit_success();
+}
+
+#[test]
+fn test_collection_royalty() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (_public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 gets a painting, royalties go to account 3
+
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            package_address,
+            "Bootstrap",
+            "bootstrap_royalty_collection",
+            manifest_args!(account_component3, dec!(10)),
+        )
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt.expect_commit_success();
+    let paintings = receipt.expect_commit(true).new_resource_addresses()[0];
+
+    let painting = NonFungibleLocalId::integer(1);
+    let btree_painting: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![painting.clone()]);
+    let mut xrd_request: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    xrd_request.insert(XRD, dec!(100));
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let no_nft_request = None as Option<Vec<NonFungibleGlobalId>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+    let no_nfts: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // account 1 offers the painting for 100 XRD
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(paintings, vec![painting.clone()]),
+        )
+        .take_non_fungibles_from_worktop(paintings, btree_painting, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Painting for XRD", // String name
+                    partner_option.clone(),
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nft_request,
+                    no_collections,
+                    Some(xrd_request),
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+
+    // account 2 buys it
+
+    let artist_before = test_runner.get_component_balance(account_component3, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "withdraw", manifest_args!(XRD, dec!(100)))
+        .take_from_worktop(XRD, dec!(100), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(no_nfts, vec![lookup.bucket("bucket1")], partner_badge, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component3, XRD), artist_before + dec!(10));
+
+    // account 1 collects what's left
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+
+    let seller_before = test_runner.get_component_balance(account_component, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            output.0,
+            vec![output.1]
+        ))
+        .call_method(component, "creator_claims_partner_assets", manifest_args!(None as Option<ManifestProof>))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_a_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_a_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, XRD), seller_before + dec!(90));
+}
+
+#[test]
+fn test_membership_discount() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+    // account 2 holds 10 membership tokens
+    let membership = test_runner.create_fungible_resource(dec!(10), 0, account_component2);
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // 1% fee, capped at 50 XRD, paid to account 1 - half off for members, free for anyone holding 10
+    let fee_config: Option<(u16, ResourceAddress, Decimal, Decimal, ComponentAddress, u16, Option<ResourceAddress>, Vec<(Decimal, u16)>)> =
+        Some((100, XRD, dec!(1), dec!(50), account_component, 0, Some(membership), vec![(dec!(1), 5000), (dec!(10), 10000)]));
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "TroveRegistry", "instantiate_registry", manifest_args!(fee_config))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let registry = receipt.expect_commit(true).new_component_addresses()[0];
+    let owner_badge = receipt.expect_commit(true).new_resource_addresses()[0];
+
+    // account 1 creates swap through the registry
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                registry,
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    // account 2 tries to pass XRD off as membership || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one.clone(), "bucket1")
+        .call_method(account_component2, "create_proof_of_amount", manifest_args!(XRD, dec!(10)))
+        .pop_from_auth_zone("member_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens.clone(), partner_badge, no_referral, Some(lookup.proof("member_proof")))
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_failure();
+
+    // account 2 accepts as a member, half of the 10 XRD fee is waived
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .call_method(account_component2, "create_proof_of_amount", manifest_args!(membership, dec!(1)))
+        .pop_from_auth_zone("member_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens, partner_badge, no_referral, Some(lookup.proof("member_proof")))
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before + dec!(995));
+
+    // account 1 sweeps the discounted fee
+
+    let balance_before = test_runner.get_component_balance(account_component, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_amount", manifest_args!(owner_badge, dec!(1)))
+        .call_method(registry, "collect_fees", manifest_args!(vec![component]))
+        .build();
+    let receipt_collect = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_collect.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, XRD), balance_before + dec!(5));
+}
+
+#[test]
+fn test_creator_approval() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 offers 1000 XRD for the car, but wants to see who's taking it first
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Approved buyers only", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions {
+                        require_approval: true,
+                        ..Default::default()
+                    }
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let localtry = output.1;
+
+    // account 2 tries to take it without waiting for approval || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one.clone(), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens.clone(), partner_badge, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_failure();
+
+    // account 2 deposits the car
+
+    let deposit_manifest = || {
+        ManifestBuilder::new()
+            .call_method(
+                account_component2,
+                "withdraw_non_fungibles",
+                manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+            )
+            .take_non_fungibles_from_worktop(secondrs.0, btree_one.clone(), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    component,
+                    "partner_deposit_nfts",
+                    manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+                )
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        deposit_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .call_method(component, "status", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let status: SwapStatus = receipty.expect_commit(true).output(2);
+    assert_eq!(status, SwapStatus::PendingApproval);
+    let receipt_resource = deposit_receipt.0;
+    let rejected_receipt = deposit_receipt.1.unwrap();
+
+    // account 2 tries to claim before account 1 has approved || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            receipt_resource,
+            vec![rejected_receipt.clone()]
+        ))
+        .pop_from_auth_zone("receipt_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_early_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_early_claim.expect_commit_failure();
+
+    // account 1 rejects, account 2 collects the car back
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry.clone()]
+        ))
+        .call_method(component, "creator_reject", manifest_args!())
+        .build();
+    let receipt_reject = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_reject.expect_commit_success();
+
+    // account 2 tries to burn the receipt their car is waiting on || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(receipt_resource, vec![rejected_receipt.clone()]),
+        )
+        .take_all_from_worktop(receipt_resource, "receipt")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "burn_deposit_receipt", manifest_args!(lookup.bucket("receipt")))
+        })
+        .build();
+    let receipt_burn = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_burn.expect_commit_failure();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            receipt_resource,
+            vec![rejected_receipt]
+        ))
+        .pop_from_auth_zone("receipt_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "partner_claim_refund", manifest_args!(lookup.proof("receipt_proof")))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_refund = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_refund.expect_commit_success();
+
+    // account 2 deposits again and account 1 approves this time
+
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        deposit_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let receipt_local = deposit_receipt.1.unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry]
+        ))
+        .call_method(component, "creator_approve", manifest_args!())
+        .build();
+    let receipt_approve = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_approve.expect_commit_success();
+
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            receipt_resource,
+            vec![receipt_local]
+        ))
+        .pop_from_auth_zone("receipt_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before + dec!(1000));
+}
+
+#[test]
+fn test_arbiter() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let partner_badge = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
+    let arbiter: Option<(ComponentAddress, i64)> = Some((account_component3, 3600));
+
+    // NFT set up complete
+
+    // account 1 offers 1000 XRD for the car, with account 3 arbitrating
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Arbitrated trade", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions {
+                        arbiter,
+                        ..Default::default()
+                    }
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let localtry = output.1;
+    let arbiter_key = NonFungibleLocalId::string("Trove_Arbiter_Key").unwrap();
+
+    // account 2 counters with their other car, which would settle without the arbiter || failure
+
+    let btree_two: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[1].clone()]);
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[1].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_two, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "propose_counter_offer",
+                manifest_args!(vec![lookup.bucket("bucket1")], None as Option<Vec<ManifestBucket>>, partner_badge, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_counter = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_counter.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_counter_offers", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let counters: Vec<NonFungibleLocalId> = receipty.expect_commit(true).output(1);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry.clone()]
+        ))
+        .call_method(component, "creator_accept_counter", manifest_args!(counters[0].clone()))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_accept.expect_commit_failure();
+
+    // account 2 deposits the car
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "partner_deposit_nfts",
+                manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let receipt_resource = deposit_receipt.0;
+    let receipt_local = deposit_receipt.1.unwrap();
+
+    let claim_manifest = || {
+        ManifestBuilder::new()
+            .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+                receipt_resource,
+                vec![receipt_local.clone()]
+            ))
+            .pop_from_auth_zone("receipt_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 2 tries to claim during the dispute window || failure
+
+    let receipt_early_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_early_claim.expect_commit_failure();
+
+    // account 2 tries to release it themselves || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "arbiter_release", manifest_args!())
+        .build();
+    let receipt_release = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_release.expect_commit_failure();
+
+    // account 3 releases the swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component3, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![arbiter_key]
+        ))
+        .call_method(component, "arbiter_release", manifest_args!())
+        .build();
+    let receipt_release = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_release.expect_commit_success();
+
+    // account 2 tries to burn the receipt the XRD is still waiting on || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(receipt_resource, vec![receipt_local.clone()]),
+        )
+        .take_all_from_worktop(receipt_resource, "receipt")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "burn_deposit_receipt", manifest_args!(lookup.bucket("receipt")))
+        })
+        .build();
+    let receipt_burn = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_burn.expect_commit_failure();
+
+    // both sides collect
+
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before + dec!(1000));
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry]
+        ))
+        .call_method(component, "creator_claims_partner_assets", manifest_args!(None as Option<ManifestProof>))
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_a_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_a_claim.expect_commit_success();
+}
+
+#[test]
+fn test_settlement_delay() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let now = test_runner.get_current_time(TimePrecision::Minute);
+    let delay: Option<i64> = Some(60 * 60);
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let partner_badge = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 offers 100 XRD for each of two cars
+
+    let propose_manifest = |car: &NonFungibleLocalId| {
+        ManifestBuilder::new()
+            .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(100)))
+            .take_from_worktop(XRD, dec!(100), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_function(
+                    package_address,
+                    "Barter",
+                    "new_trade_proposal",
+                    manifest_args!(
+                        "Locked trade", // String name
+                        partner_option.clone(),
+                        Some(vec![lookup.bucket("bucket1")]),
+                        no_nfts.clone(),
+                        Some(vec![NonFungibleGlobalId::new(secondrs.0, car.clone())]),
+                        no_collections.clone(),
+                        none_hashmap_fungibles.clone(),
+                        no_registry,
+                        ProposalOptions {
+                            settlement_delay: delay,
+                            ..Default::default()
+                        }
+                    ),
+                )
+            })
+            .call_method(
+                account_component,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    let mut swaps: Vec<ComponentAddress> = Vec::new();
+    let mut receipts: Vec<NonFungibleLocalId> = Vec::new();
+    let mut receipt_resource = XRD;
+    let mut creator_keys: Vec<(ResourceAddress, NonFungibleLocalId)> = Vec::new();
+
+    for car in secondrs.1[..2].iter() {
+        let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+            propose_manifest(car),
+            vec![NonFungibleGlobalId::from_public_key(&public_key)],
+        );
+        receipt_new_trade_proposal.expect_commit_success();
+        let swap = receipt_new_trade_proposal
+            .expect_commit(true).new_component_addresses()[0];
+
+        let manifest = ManifestBuilder::new()
+            .call_method(swap, "get_badge", manifest_args!())
+            .build();
+        let receipty = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&public_key)],
+        );
+        let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+            receipty.expect_commit(true).output(1);
+        creator_keys.push((output.0, output.1));
+
+        // account 2 funds it
+
+        let btree_car: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![car.clone()]);
+        let manifest = ManifestBuilder::new()
+            .call_method(
+                account_component2,
+                "withdraw_non_fungibles",
+                manifest_args!(secondrs.0, vec![car.clone()]),
+            )
+            .take_non_fungibles_from_worktop(secondrs.0, btree_car, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    swap,
+                    "partner_deposit_nfts",
+                    manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+                )
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+        );
+        receipt_b_deposit.expect_commit_success();
+
+        let manifest = ManifestBuilder::new()
+            .call_method(swap, "get_deposit_receipt", manifest_args!())
+            .build();
+        let receipty = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+        );
+        let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+            receipty.expect_commit(true).output(1);
+
+        swaps.push(swap);
+        receipt_resource = deposit_receipt.0;
+        receipts.push(deposit_receipt.1.unwrap());
+    }
+
+    let claim_manifest = |swap: ComponentAddress, receipt_resource: ResourceAddress, receipt_local: NonFungibleLocalId| {
+        ManifestBuilder::new()
+            .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+                receipt_resource,
+                vec![receipt_local]
+            ))
+            .pop_from_auth_zone("receipt_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(swap, "partner_claims_creator_assets", manifest_args!(partner_badge, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 2 tries to claim straight away || failure
+
+    let receipt_early_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(swaps[0], receipt_resource, receipts[0].clone()),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_early_claim.expect_commit_failure();
+
+    // account 1 tries to pull out during the delay || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            creator_keys[0].0,
+            vec![creator_keys[0].1.clone()]
+        ))
+        .call_method(swaps[0], "creator_cancel", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_cancel = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_cancel.expect_commit_failure();
+
+    // account 1 disputes the second swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            creator_keys[1].0,
+            vec![creator_keys[1].1.clone()]
+        ))
+        .pop_from_auth_zone("key_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(swaps[1], "raise_dispute", manifest_args!(lookup.proof("key_proof")))
+        })
+        .build();
+    let receipt_dispute = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_dispute.expect_commit_success();
+
+    // move the clock past the delay
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(1),
+        (now.seconds_since_unix_epoch + 2 * 60 * 60) * 1000,
+    );
+
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(swaps[0], receipt_resource, receipts[0].clone()),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before + dec!(100));
+
+    // account 2 tries to claim the disputed swap || failure
+
+    let receipt_disputed_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(swaps[1], receipt_resource, receipts[1].clone()),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_disputed_claim.expect_commit_failure();
+
+    // account 2 takes their car back instead
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            receipt_resource,
+            vec![receipts[1].clone()]
+        ))
+        .pop_from_auth_zone("receipt_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(swaps[1], "partner_cancel", manifest_args!(partner_badge, lookup.proof("receipt_proof")))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_cancel = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_cancel.expect_commit_success();
+}
+
+#[test]
+fn test_english_auction() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![firstrs.1[0].clone()]);
+    let now = test_runner.get_current_time(TimePrecision::Minute);
+    let ends_at: Instant = now.add_hours(1).unwrap();
+    let extension_window: Option<i64> = Some(10 * 60);
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+
+    // NFT set up complete
+
+    // account 1 puts a car up for auction
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(firstrs.0, vec![firstrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(firstrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "EnglishAuction",
+                "new_auction",
+                manifest_args!(
+                    "Car auction", // String name
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    XRD,
+                    dec!(100),
+                    dec!(10),
+                    ends_at,
+                    extension_window
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_auction = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_auction.expect_commit_success();
+    let auction = receipt_new_auction
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(auction, "get_badge", manifest_args!())
+        .call_method(auction, "get_bid_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let seller_key: (ResourceAddress, NonFungibleLocalId) = receipty.expect_commit(true).output(1);
+    let bid_receipt: ResourceAddress = receipty.expect_commit(true).output(2);
+
+    let bid_manifest = |account: ComponentAddress, amount: Decimal| {
+        ManifestBuilder::new()
+            .call_method(account, "withdraw", manifest_args!(XRD, amount))
+            .take_from_worktop(XRD, amount, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, "place_bid", manifest_args!(lookup.bucket("bucket1")))
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 2 opens the bidding
+
+    let receipt_bid = test_runner.execute_manifest_ignoring_fee(
+        bid_manifest(account_component2, dec!(100)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_bid.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(auction, "get_high_bid", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let high_bid: Option<(NonFungibleLocalId, Decimal)> = receipty.expect_commit(true).output(1);
+    let account2_bid = high_bid.unwrap().0;
+
+    // account 3 doesn't bid enough || failure
+
+    let receipt_bid = test_runner.execute_manifest_ignoring_fee(
+        bid_manifest(account_component3, dec!(105)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_bid.expect_commit_failure();
+
+    // account 3 outbids account 2
+
+    let receipt_bid = test_runner.execute_manifest_ignoring_fee(
+        bid_manifest(account_component3, dec!(110)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_bid.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(auction, "get_high_bid", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    let high_bid: Option<(NonFungibleLocalId, Decimal)> = receipty.expect_commit(true).output(1);
+    let account3_bid = high_bid.unwrap().0;
+
+    // account 2 takes their bid back
+
+    let bidder_before = test_runner.get_component_balance(account_component2, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            bid_receipt,
+            vec![account2_bid]
+        ))
+        .pop_from_auth_zone("bid_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(auction, "refund_bid", manifest_args!(lookup.proof("bid_proof")))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_refund = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_refund.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), bidder_before + dec!(100));
+
+    let claim_manifest = || {
+        ManifestBuilder::new()
+            .call_method(account_component3, "create_proof_of_non_fungibles", manifest_args!(
+                bid_receipt,
+                vec![account3_bid.clone()]
+            ))
+            .pop_from_auth_zone("bid_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, "winner_claim", manifest_args!(lookup.proof("bid_proof")))
+            })
+            .call_method(
+                account_component3,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 3 tries to claim early || failure
+
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_claim.expect_commit_failure();
+
+    // move the clock past the end
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(1),
+        (now.seconds_since_unix_epoch + 2 * 60 * 60) * 1000,
+    );
+
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_claim.expect_commit_success();
+
+    let seller_before = test_runner.get_component_balance(account_component, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            seller_key.0,
+            vec![seller_key.1]
+        ))
+        .call_method(auction, "seller_claim", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_seller = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_seller.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, XRD), seller_before + dec!(110));
+}
+
+#[test]
+fn test_dutch_listing() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![firstrs.1[0].clone()]);
+    let now = test_runner.get_current_time(TimePrecision::Minute);
+    let ends_at: Instant = now.add_hours(10).unwrap();
+    let no_step = None as Option<i64>;
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+
+    // NFT set up complete
+
+    // account 1 lists a car, 1000 XRD falling to 100 over ten hours
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(firstrs.0, vec![firstrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(firstrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "DutchListing",
+                "new_dutch_listing",
+                manifest_args!(
+                    "Car listing", // String name
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    XRD,
+                    dec!(1000),
+                    dec!(100),
+                    ends_at,
+                    no_step
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_listing = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_listing.expect_commit_success();
+    let listing = receipt_new_listing
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(listing, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let seller_key: (ResourceAddress, NonFungibleLocalId) = receipty.expect_commit(true).output(1);
+
+    let take_manifest = |account: ComponentAddress, amount: Decimal| {
+        ManifestBuilder::new()
+            .call_method(account, "withdraw", manifest_args!(XRD, amount))
+            .take_from_worktop(XRD, amount, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(listing, "take_listing", manifest_args!(lookup.bucket("bucket1")))
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // move the clock halfway, price should be 550
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(1),
+        (now.seconds_since_unix_epoch + 5 * 60 * 60) * 1000,
+    );
+
+    let manifest = ManifestBuilder::new()
+        .call_method(listing, "current_price", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let price: Decimal = receipty.expect_commit(true).output(1);
+    assert_eq!(price, dec!(550));
+
+    // account 2 doesn't pay enough || failure
+
+    let receipt_take = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(account_component2, dec!(500)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_take.expect_commit_failure();
+
+    // account 2 overpays and gets the change back
+
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_take = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(account_component2, dec!(600)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_take.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before - dec!(550));
+    assert_eq!(test_runner.get_component_balance(account_component2, firstrs.0), dec!(1));
+
+    // account 3 is too late || failure
+
+    let receipt_take = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(account_component3, dec!(600)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_take.expect_commit_failure();
+
+    let seller_before = test_runner.get_component_balance(account_component, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            seller_key.0,
+            vec![seller_key.1]
+        ))
+        .call_method(listing, "seller_claim", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_seller = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_seller.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, XRD), seller_before + dec!(550));
+}
+
+#[test]
+fn test_sealed_auction() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2, 3 and 4
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    let (public_key4, _private_key4, account_component4) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![firstrs.1[0].clone()]);
+    let now = test_runner.get_current_time(TimePrecision::Minute);
+    let commit_ends_at: Instant = now.add_hours(1).unwrap();
+    let reveal_ends_at: Instant = now.add_hours(2).unwrap();
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+
+    // NFT set up complete
+
+    // account 1 puts a car up for a sealed auction
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(firstrs.0, vec![firstrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(firstrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "SealedAuction",
+                "new_sealed_auction",
+                manifest_args!(
+                    "Car sealed auction", // String name
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    XRD,
+                    dec!(100),
+                    commit_ends_at,
+                    reveal_ends_at
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_auction = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_auction.expect_commit_success();
+    let auction = receipt_new_auction
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(auction, "get_badge", manifest_args!())
+        .call_method(auction, "get_bid_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let seller_key: (ResourceAddress, NonFungibleLocalId) = receipty.expect_commit(true).output(1);
+    let bid_receipt: ResourceAddress = receipty.expect_commit(true).output(2);
+
+    let commit_manifest = |account: ComponentAddress, amount: Decimal, salt: &str, deposit: Decimal| {
+        let commitment = hash(scrypto_encode(&(amount, salt.to_string())).unwrap());
+        ManifestBuilder::new()
+            .call_method(account, "withdraw", manifest_args!(XRD, deposit))
+            .take_from_worktop(XRD, deposit, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, "commit_bid", manifest_args!(commitment, lookup.bucket("bucket1")))
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    let reveal_manifest = |account: ComponentAddress, amount: Decimal, salt: &str| {
+        ManifestBuilder::new()
+            .call_method(account, "create_proof_of_amount", manifest_args!(bid_receipt, dec!(1)))
+            .pop_from_auth_zone("bid_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, "reveal_bid", manifest_args!(
+                    lookup.proof("bid_proof"),
+                    amount,
+                    salt.to_string()
+                ))
+            })
+            .build()
+    };
+
+    // refunds and the winner's claim both just take the receipt
+    let receipt_manifest = |account: ComponentAddress, method: &str| {
+        ManifestBuilder::new()
+            .call_method(account, "create_proof_of_amount", manifest_args!(bid_receipt, dec!(1)))
+            .pop_from_auth_zone("bid_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, method, manifest_args!(lookup.proof("bid_proof")))
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // accounts 2, 3 and 4 commit
+
+    let receipt_commit = test_runner.execute_manifest_ignoring_fee(
+        commit_manifest(account_component2, dec!(150), "salt2", dec!(200)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_commit.expect_commit_success();
+    let receipt_commit = test_runner.execute_manifest_ignoring_fee(
+        commit_manifest(account_component3, dec!(120), "salt3", dec!(120)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_commit.expect_commit_success();
+    let receipt_commit = test_runner.execute_manifest_ignoring_fee(
+        commit_manifest(account_component4, dec!(130), "salt4", dec!(130)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key4)],
+    );
+    receipt_commit.expect_commit_success();
+
+    // account 3 reveals too early || failure
+
+    let receipt_reveal = test_runner.execute_manifest_ignoring_fee(
+        reveal_manifest(account_component3, dec!(120), "salt3"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_reveal.expect_commit_failure();
+
+    // move the clock past the commit phase
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(1),
+        (now.seconds_since_unix_epoch + 90 * 60) * 1000,
+    );
+
+    // account 2 gets their salt wrong || failure
+
+    let receipt_reveal = test_runner.execute_manifest_ignoring_fee(
+        reveal_manifest(account_component2, dec!(150), "salt3"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_reveal.expect_commit_failure();
+
+    let receipt_reveal = test_runner.execute_manifest_ignoring_fee(
+        reveal_manifest(account_component2, dec!(150), "salt2"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_reveal.expect_commit_success();
+    let receipt_reveal = test_runner.execute_manifest_ignoring_fee(
+        reveal_manifest(account_component3, dec!(120), "salt3"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_reveal.expect_commit_success();
+
+    // account 3 wants their deposit back before reveals close || failure
+
+    let receipt_refund = test_runner.execute_manifest_ignoring_fee(
+        receipt_manifest(account_component3, "refund_bid"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_refund.expect_commit_failure();
+
+    // move the clock past the reveal phase
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(2),
+        (now.seconds_since_unix_epoch + 3 * 60 * 60) * 1000,
+    );
+
+    // the losing and unrevealed bids come back
+
+    let bidder_before = test_runner.get_component_balance(account_component3, XRD);
+    let receipt_refund = test_runner.execute_manifest_ignoring_fee(
+        receipt_manifest(account_component3, "refund_bid"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_refund.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component3, XRD), bidder_before + dec!(120));
+
+    let bidder_before = test_runner.get_component_balance(account_component4, XRD);
+    let receipt_refund = test_runner.execute_manifest_ignoring_fee(
+        receipt_manifest(account_component4, "refund_bid"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key4)],
+    );
+    receipt_refund.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component4, XRD), bidder_before + dec!(130));
+
+    // account 2 takes the car and the rest of their deposit
+
+    let winner_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        receipt_manifest(account_component2, "winner_claim"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), winner_before + dec!(50));
+    assert_eq!(test_runner.get_component_balance(account_component2, firstrs.0), dec!(1));
+
+    let seller_before = test_runner.get_component_balance(account_component, XRD);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            seller_key.0,
+            vec![seller_key.1]
+        ))
+        .call_method(auction, "seller_claim", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_seller = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_seller.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, XRD), seller_before + dec!(150));
+}
+
+#[test]
+fn test_staged_deposit() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let request: Option<Vec<NonFungibleGlobalId>> = Some(vec![
+        NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone()),
+        NonFungibleGlobalId::new(secondrs.0, secondrs.1[1].clone()),
+    ]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let partner_badge = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    // account 1 offers 1000 XRD for both cars
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Two cars", // String name
+                    partner_option.clone(),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let localtry = output.1;
+
+    // account 2 deposits the first car
+
+    let btree_first: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_first, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "partner_deposit_nfts",
+                manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let receipt_resource = deposit_receipt.0;
+    let receipt_local = deposit_receipt.1.unwrap();
+
+    // account 2 attempts to claim with only one car in || failure
+
+    let claim_manifest = || {
+        ManifestBuilder::new()
+            .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+                receipt_resource,
+                vec![receipt_local.clone()]
+            ))
+            .pop_from_auth_zone("receipt_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+    let receipt_early_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_early_claim.expect_commit_failure();
+
+    // account 2 attempts to deposit the second car without their receipt || failure
+
+    let btree_second: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[1].clone()]);
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[1].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_second.clone(), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "partner_deposit_nfts",
+                manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_failure();
+
+    // account 2 deposits the second car under the same receipt, then claims the XRD
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            receipt_resource,
+            vec![receipt_local.clone()]
+        ))
+        .pop_from_auth_zone("receipt_proof")
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[1].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_second, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "partner_deposit_nfts",
+                manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, Some(lookup.proof("receipt_proof")), no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        claim_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before + dec!(1000));
+
+    // account 1 collects both cars
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry]
+        ))
+        .call_method(
+            component,
+            "creator_claims_partner_assets",
+            manifest_args!(None as Option<ManifestProof>),
+        )
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_a_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_a_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, secondrs.0), dec!(2));
+}
+
+#[test]
+fn test_partner_claim_key() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2 - virtual, so it carries the owner_keys the key claim checks against
+    let (public_key2, _private_key2, account_component2) = test_runner.new_virtual_account();
+    // Create an account 3
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+
+    // NFT set up complete
+
+    // account 2 refuses anything they've not seen before
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "set_default_deposit_rule",
+            manifest_args!(DefaultDepositRule::Reject),
+        )
+        .build();
+    let receipt_rule = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_rule.expect_commit_success();
+
+    // account 1 offers 1000 XRD for the car, to account 2 only
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Just for you", // String name
+                    Some(vec![account_component2]),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let partner_badge = output.3.unwrap();
+    let partner_badge_local = output.4[0].clone();
+
+    // the key never made it into account 2
+    assert_eq!(test_runner.get_component_balance(account_component2, partner_badge), dec!(0));
+
+    // account 3 tries to claim account 2's key || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "partner_claim_key", manifest_args!(account_component2))
+        .call_method(
+            account_component3,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_steal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_steal.expect_commit_failure();
+
+    // account 2 claims their key
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "partner_claim_key", manifest_args!(account_component2))
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_claim_key = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim_key.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, partner_badge), dec!(1));
+
+    // and takes the swap with it
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            partner_badge,
+            vec![partner_badge_local]
+        ))
+        .pop_from_auth_zone("partner_proof")
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens, Some(lookup.proof("partner_proof")), no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before + dec!(1000));
+}
+
+#[test]
+fn test_holder_gate() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Create an account 3
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    let gate_token = test_runner.create_fungible_resource(dec!(10), 0, account_component2);
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![firstrs.1[0].clone()]);
+    let mut token_request: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    token_request.insert(XRD, dec!(500));
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+    let no_request = None as Option<Vec<NonFungibleGlobalId>>;
+    let no_nfts: Vec<ManifestBucket> = Vec::new();
+
+    // NFT set up complete
+
+    // account 1 offers the car for 500 XRD, to holders of 5 gate tokens
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(firstrs.0, vec![firstrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(firstrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Holders only", // String name
+                    partner_option,
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_request,
+                    no_collections,
+                    Some(token_request),
+                    no_registry,
+                    ProposalOptions {
+                        holder_gate: Some((gate_token, dec!(5))),
+                        ..Default::default()
+                    }
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    // account 3 holds no gate tokens || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component3, "withdraw", manifest_args!(XRD, dec!(500)))
+        .take_from_worktop(XRD, dec!(500), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(no_nfts.clone(), vec![lookup.bucket("bucket1")], None as Option<ManifestProof>, no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component3,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_non_holder = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_non_holder.expect_commit_failure();
+
+    // account 2 passes account 3 a single gate token
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "withdraw", manifest_args!(gate_token, dec!(1)))
+        .call_method(
+            account_component3,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_transfer = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![
+            NonFungibleGlobalId::from_public_key(&public_key2),
+            NonFungibleGlobalId::from_public_key(&public_key3),
+        ],
+    );
+    receipt_transfer.expect_commit_success();
+
+    // gated swaps take a proof of the gate token in the badge slot
+
+    let take_manifest = |account: ComponentAddress, holding: Decimal| {
+        ManifestBuilder::new()
+            .call_method(account, "create_proof_of_amount", manifest_args!(gate_token, holding))
+            .pop_from_auth_zone("gate_proof")
+            .call_method(account, "withdraw", manifest_args!(XRD, dec!(500)))
+            .take_from_worktop(XRD, dec!(500), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    component,
+                    "accept_trade",
+                    manifest_args!(no_nfts.clone(), vec![lookup.bucket("bucket1")], Some(lookup.proof("gate_proof")), no_referral, None as Option<ManifestProof>)
+                )
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 3 tries with their 1 gate token || failure
+
+    let receipt_under = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(account_component3, dec!(1)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_under.expect_commit_failure();
+
+    // account 2 takes the swap with 5 of theirs
+
+    let receipt_holder = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(account_component2, dec!(5)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_holder.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, firstrs.0), dec!(1));
+}
+
+#[test]
+fn test_multiple_partners() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (_public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Create an account 3
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![firstrs.1[0].clone()]);
+    let mut token_request: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    token_request.insert(XRD, dec!(500));
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+    let no_request = None as Option<Vec<NonFungibleGlobalId>>;
+    let no_nfts: Vec<ManifestBucket> = Vec::new();
+
+    // NFT set up complete
+
+    let proposal_manifest = |partners: Vec<ComponentAddress>| {
+        ManifestBuilder::new()
+            .call_method(
+                account_component,
+                "withdraw_non_fungibles",
+                manifest_args!(firstrs.0, vec![firstrs.1[0].clone()]),
+            )
+            .take_non_fungibles_from_worktop(firstrs.0, btree_one.clone(), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_function(
+                    package_address,
+                    "Barter",
+                    "new_trade_proposal",
+                    manifest_args!(
+                        "For either of you", // String name
+                        Some(partners),
+                        no_tokens.clone(),
+                        Some(vec![lookup.bucket("bucket1")]),
+                        no_request.clone(),
+                        no_collections.clone(),
+                        Some(token_request.clone()),
+                        no_registry,
+                        ProposalOptions::default()
+                    ),
+                )
+            })
+            .call_method(
+                account_component,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 1 names account 2 twice || failure
+
+    let receipt_duplicate = test_runner.execute_manifest_ignoring_fee(
+        proposal_manifest(vec![account_component2, account_component2]),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_duplicate.expect_commit_failure();
+
+    // account 1 offers the car to accounts 2 and 3
+
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        proposal_manifest(vec![account_component2, account_component3]),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let localtry = output.1;
+    let partner_badge = output.3.unwrap();
+    let key_1 = NonFungibleLocalId::string("Trove_Partner_Key_1").unwrap();
+    let key_2 = NonFungibleLocalId::string("Trove_Partner_Key_2").unwrap();
+    assert_eq!(output.4, vec![key_1.clone(), key_2.clone()]);
+    assert_eq!(test_runner.get_component_balance(account_component2, partner_badge), dec!(1));
+    assert_eq!(test_runner.get_component_balance(account_component3, partner_badge), dec!(1));
+
+    let take_manifest = |account: ComponentAddress, key: NonFungibleLocalId| {
+        ManifestBuilder::new()
+            .call_method(account, "create_proof_of_non_fungibles", manifest_args!(
+                partner_badge,
+                vec![key]
+            ))
+            .pop_from_auth_zone("partner_proof")
+            .call_method(account, "withdraw", manifest_args!(XRD, dec!(500)))
+            .take_from_worktop(XRD, dec!(500), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    component,
+                    "accept_trade",
+                    manifest_args!(no_nfts.clone(), vec![lookup.bucket("bucket1")], Some(lookup.proof("partner_proof")), no_referral, None as Option<ManifestProof>)
+                )
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 1's creator key is from the same resource, but isn't one of the partner keys || failure
+
+    let receipt_creator_take = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(account_component, localtry),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_creator_take.expect_commit_failure();
+    assert_eq!(rebadge, partner_badge);
+
+    // account 3 takes the swap with the second key
+
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(account_component3, key_2.clone()),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_b_accept.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component3, firstrs.0), dec!(1));
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_proposal", manifest_args!())
+        .build();
+    let receipt_view = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let view: ProposalView = receipt_view.expect_commit(true).output(1);
+    assert_eq!(view.status, SwapStatus::Settled);
+    assert_eq!(view.filled_by, Some(key_2));
+}
+
+#[test]
+fn test_swap_status() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let partner_badge = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
+
+    // NFT set up complete
+
+    let proposal_manifest = || {
+        ManifestBuilder::new()
+            .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+            .take_from_worktop(XRD, dec!(1000), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_function(
+                    package_address,
+                    "Barter",
+                    "new_trade_proposal",
+                    manifest_args!(
+                        "My new trade!", // String name
+                        partner_option.clone(),
+                        Some(vec![lookup.bucket("bucket1")]),
+                        no_nfts.clone(),
+                        request.clone(),
+                        no_collections.clone(),
+                        none_hashmap_fungibles.clone(),
+                        no_registry,
+                        ProposalOptions::default()
+                    ),
+                )
+            })
+            .call_method(
+                account_component,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+    let status_manifest = |component: ComponentAddress| {
+        ManifestBuilder::new()
+            .call_method(component, "status", manifest_args!())
+            .build()
+    };
+    let cancel_manifest = |component: ComponentAddress, rebadge: ResourceAddress, localtry: NonFungibleLocalId| {
+        ManifestBuilder::new()
+            .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+                rebadge,
+                vec![localtry]
+            ))
+            .call_method(component, "creator_cancel", manifest_args!())
+            .call_method(
+                account_component,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // account 1 offers 1000 XRD for the car
+
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        proposal_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let localtry = output.1;
+
+    let status: SwapStatus = test_runner
+        .execute_manifest_ignoring_fee(status_manifest(component), vec![NonFungibleGlobalId::from_public_key(&public_key)])
+        .expect_commit(true)
+        .output(1);
+    assert_eq!(status, SwapStatus::Open);
+
+    // account 2 deposits the car
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "partner_deposit_nfts",
+                manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let status: SwapStatus = test_runner
+        .execute_manifest_ignoring_fee(status_manifest(component), vec![NonFungibleGlobalId::from_public_key(&public_key)])
+        .expect_commit(true)
+        .output(1);
+    assert_eq!(status, SwapStatus::PartnerFunded);
+
+    // account 2 claims the XRD
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            deposit_receipt.0,
+            vec![deposit_receipt.1.unwrap()]
+        ))
+        .pop_from_auth_zone("receipt_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(component, "partner_claims_creator_assets", manifest_args!(partner_badge, lookup.proof("receipt_proof"), None as Option<ManifestProof>))
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim.expect_commit_success();
+
+    let status: SwapStatus = test_runner
+        .execute_manifest_ignoring_fee(status_manifest(component), vec![NonFungibleGlobalId::from_public_key(&public_key)])
+        .expect_commit(true)
+        .output(1);
+    assert_eq!(status, SwapStatus::Settled);
+
+    // account 1 tries to cancel the settled swap || failure
+
+    let receipt_cancel = test_runner.execute_manifest_ignoring_fee(
+        cancel_manifest(component, rebadge, localtry),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_cancel.expect_commit_failure();
+
+    let status: SwapStatus = test_runner
+        .execute_manifest_ignoring_fee(status_manifest(component), vec![NonFungibleGlobalId::from_public_key(&public_key)])
+        .expect_commit(true)
+        .output(1);
+    assert_eq!(status, SwapStatus::Settled);
+
+    // account 1 offers the same again, then cancels
+
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        proposal_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+
+    let status: SwapStatus = test_runner
+        .execute_manifest_ignoring_fee(status_manifest(component), vec![NonFungibleGlobalId::from_public_key(&public_key)])
+        .expect_commit(true)
+        .output(1);
+    assert_eq!(status, SwapStatus::Open);
+
+    let receipt_cancel = test_runner.execute_manifest_ignoring_fee(
+        cancel_manifest(component, output.0, output.1),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_cancel.expect_commit_success();
+
+    let status: SwapStatus = test_runner
+        .execute_manifest_ignoring_fee(status_manifest(component), vec![NonFungibleGlobalId::from_public_key(&public_key)])
+        .expect_commit(true)
+        .output(1);
+    assert_eq!(status, SwapStatus::Cancelled);
+}
+
+#[test]
+fn test_ring_foreign_component() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+
+    // NFT set up complete
+
+    // account 1 offers 1000 XRD for the car
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "My new trade!", // String name
+                    partner_option,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request,
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+    let swap = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    // account 2 wraps the car in a fake swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(package_address, "FakeSwap", "instantiate", manifest_args!(vec![lookup.bucket("bucket1")]))
+        })
+        .build();
+    let receipt_fake = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_fake.expect_commit_success();
+    let fake_swap = receipt_fake.expect_commit(true).new_component_addresses()[0];
+
+    // the car would fill the swap's request, but the fake swap isn't a Trove swap | expect failure
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "RingSettlement", "settle_ring", manifest_args!(vec![swap, fake_swap]))
+        .build();
+    let receipt_ring = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_ring.expect_commit_failure();
+
+    assert_eq!(test_runner.get_component_balance(swap, XRD), dec!(1000));
+    assert_eq!(test_runner.get_component_balance(fake_swap, XRD), dec!(0));
+}
+
+#[test]
+fn test_sealed_auction_divisibility() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // whole tokens only - accounts 2 and 3 get 500 each
+    let bid_token = test_runner.create_fungible_resource(dec!(1000), 0, account_component2);
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "withdraw", manifest_args!(bid_token, dec!(500)))
+        .call_method(
+            account_component3,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_transfer = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![
+            NonFungibleGlobalId::from_public_key(&public_key2),
+            NonFungibleGlobalId::from_public_key(&public_key3),
+        ],
+    );
+    receipt_transfer.expect_commit_success();
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![firstrs.1[0].clone()]);
+    let now = test_runner.get_current_time(TimePrecision::Minute);
+    let commit_ends_at: Instant = now.add_hours(1).unwrap();
+    let reveal_ends_at: Instant = now.add_hours(2).unwrap();
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+
+    // NFT set up complete
+
+    // account 1 puts a car up for a sealed auction
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component,
+            "withdraw_non_fungibles",
+            manifest_args!(firstrs.0, vec![firstrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(firstrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "SealedAuction",
+                "new_sealed_auction",
+                manifest_args!(
+                    "Car sealed auction", // String name
+                    no_tokens,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    bid_token,
+                    dec!(100),
+                    commit_ends_at,
+                    reveal_ends_at
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_auction = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_auction.expect_commit_success();
+    let auction = receipt_new_auction
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(auction, "get_badge", manifest_args!())
+        .call_method(auction, "get_bid_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let seller_key: (ResourceAddress, NonFungibleLocalId) = receipty.expect_commit(true).output(1);
+    let bid_receipt: ResourceAddress = receipty.expect_commit(true).output(2);
+
+    let commit_manifest = |account: ComponentAddress, amount: Decimal, salt: &str, deposit: Decimal| {
+        let commitment = hash(scrypto_encode(&(amount, salt.to_string())).unwrap());
+        ManifestBuilder::new()
+            .call_method(account, "withdraw", manifest_args!(bid_token, deposit))
+            .take_from_worktop(bid_token, deposit, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, "commit_bid", manifest_args!(commitment, lookup.bucket("bucket1")))
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    let reveal_manifest = |account: ComponentAddress, amount: Decimal, salt: &str| {
+        ManifestBuilder::new()
+            .call_method(account, "create_proof_of_amount", manifest_args!(bid_receipt, dec!(1)))
+            .pop_from_auth_zone("bid_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, "reveal_bid", manifest_args!(
+                    lookup.proof("bid_proof"),
+                    amount,
+                    salt.to_string()
+                ))
+            })
+            .build()
+    };
+
+    let receipt_manifest = |account: ComponentAddress, method: &str| {
+        ManifestBuilder::new()
+            .call_method(account, "create_proof_of_amount", manifest_args!(bid_receipt, dec!(1)))
+            .pop_from_auth_zone("bid_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(auction, method, manifest_args!(lookup.proof("bid_proof")))
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // accounts 2 and 3 commit
+
+    let receipt_commit = test_runner.execute_manifest_ignoring_fee(
+        commit_manifest(account_component2, dec!("150.5"), "salt2", dec!(200)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_commit.expect_commit_success();
+    let receipt_commit = test_runner.execute_manifest_ignoring_fee(
+        commit_manifest(account_component3, dec!(120), "salt3", dec!(120)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_commit.expect_commit_success();
+
+    // move the clock past the commit phase
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(1),
+        (now.seconds_since_unix_epoch + 90 * 60) * 1000,
+    );
+
+    // 150.5 can't be taken out of a deposit in whole tokens || failure
+
+    let receipt_reveal = test_runner.execute_manifest_ignoring_fee(
+        reveal_manifest(account_component2, dec!("150.5"), "salt2"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_reveal.expect_commit_failure();
+
+    let receipt_reveal = test_runner.execute_manifest_ignoring_fee(
+        reveal_manifest(account_component3, dec!(120), "salt3"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_reveal.expect_commit_success();
+
+    // move the clock past the reveal phase
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(2),
+        (now.seconds_since_unix_epoch + 3 * 60 * 60) * 1000,
+    );
+
+    // account 2's unrevealed deposit comes back
+
+    let bidder_before = test_runner.get_component_balance(account_component2, bid_token);
+    let receipt_refund = test_runner.execute_manifest_ignoring_fee(
+        receipt_manifest(account_component2, "refund_bid"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_refund.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, bid_token), bidder_before + dec!(200));
+
+    // account 3 takes the car, account 1 the 120
+
+    let receipt_claim = test_runner.execute_manifest_ignoring_fee(
+        receipt_manifest(account_component3, "winner_claim"),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_claim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component3, firstrs.0), dec!(1));
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            seller_key.0,
+            vec![seller_key.1]
+        ))
+        .call_method(auction, "seller_claim", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_seller = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_seller.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, bid_token), dec!(120));
+}
+
+#[test]
+fn test_staged_tokens() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Create an account 3
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    let token = test_runner.create_fungible_resource(dec!(1000), 18, account_component2);
+
+    let mut request: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    request.insert(token, dec!(300));
+    request.insert(XRD, dec!(100));
+    let no_request: Option<Vec<NonFungibleGlobalId>> = None;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let partner_badge = None as Option<ManifestProof>;
+    let no_receipt = None as Option<ManifestProof>;
+
+    // account 1 offers 1000 XRD for 300 tokens and 100 XRD
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Tokens", // String name
+                    partner_option,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    no_request,
+                    no_collections,
+                    Some(request),
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    // account 2 deposits 100 tokens
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "withdraw", manifest_args!(token, dec!(100)))
+        .take_from_worktop(token, dec!(100), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "partner_deposit_tokens",
+                manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_deposit_receipt", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let deposit_receipt: (ResourceAddress, Option<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let receipt_resource = deposit_receipt.0;
+    let receipt_local = deposit_receipt.1.unwrap();
+
+    // follow-up deposits all go in under account 2's receipt
+
+    let deposit_manifest = |amounts: Vec<(ResourceAddress, Decimal)>| {
+        let mut builder = ManifestBuilder::new()
+            .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+                receipt_resource,
+                vec![receipt_local.clone()]
+            ))
+            .pop_from_auth_zone("receipt_proof");
+        let mut names: Vec<String> = Vec::new();
+        for (index, (resource_address, amount)) in amounts.into_iter().enumerate() {
+            let name = format!("bucket{}", index);
+            builder = builder
+                .call_method(account_component2, "withdraw", manifest_args!(resource_address, amount))
+                .take_from_worktop(resource_address, amount, name.as_str());
+            names.push(name);
+        }
+        builder
+            .with_name_lookup(|builder, lookup| {
+                let buckets: Vec<ManifestBucket> = names.iter().map(|name| lookup.bucket(name.as_str())).collect();
+                builder.call_method(
+                    component,
+                    "partner_deposit_tokens",
+                    manifest_args!(buckets, partner_badge, Some(lookup.proof("receipt_proof")), no_referral)
+                )
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // 350 would be over the 300 requested || failure
+
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        deposit_manifest(vec![(token, dec!(250))]),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_failure();
+
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        deposit_manifest(vec![(token, dec!(200)), (XRD, dec!(100))]),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_proposal", manifest_args!())
+        .build();
+    let receipt_view = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let view: ProposalView = receipt_view.expect_commit(true).output(1);
+    assert_eq!(view.status, SwapStatus::PartnerFunded);
+    let (_, _, tokens_in, _, tokens_received, _) = view.partner_deposit.unwrap();
+    assert!(tokens_in);
+    assert_eq!(tokens_received.get(&token), Some(&dec!(300)));
+
+    // account 2 cancels one asset at a time
+
+    let cancel_manifest = || {
+        ManifestBuilder::new()
+            .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+                receipt_resource,
+                vec![receipt_local.clone()]
+            ))
+            .pop_from_auth_zone("receipt_proof")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(component, "partner_cancel_page", manifest_args!(partner_badge, lookup.proof("receipt_proof"), 1u32))
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+    let receipt_cancel = test_runner.execute_manifest_ignoring_fee(
+        cancel_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_cancel.expect_commit_success();
+
+    // account 3 attempts to fund the swap while account 2's deposit is still in it || failure
+
+    let fund_manifest = || {
+        ManifestBuilder::new()
+            .call_method(account_component3, "withdraw", manifest_args!(XRD, dec!(100)))
+            .take_from_worktop(XRD, dec!(100), "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(
+                    component,
+                    "partner_deposit_tokens",
+                    manifest_args!(vec![lookup.bucket("bucket1")], partner_badge, no_receipt, no_referral)
+                )
+            })
+            .call_method(
+                account_component3,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+    let receipt_c_deposit = test_runner.execute_manifest_ignoring_fee(
+        fund_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_c_deposit.expect_commit_failure();
+
+    // account 2 takes back the rest, then the swap is free for account 3
+
+    let receipt_cancel = test_runner.execute_manifest_ignoring_fee(
+        cancel_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_cancel.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, token), dec!(1000));
+
+    let receipt_c_deposit = test_runner.execute_manifest_ignoring_fee(
+        fund_manifest(),
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_c_deposit.expect_commit_success();
+}
+
+#[test]
+fn test_dutch_divisibility() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1 and 2
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 1 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let firstrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    // payments are in whole tokens only
+    let payment_token = test_runner.create_fungible_resource(dec!(1000), 0, account_component2);
+
+    let now = test_runner.get_current_time(TimePrecision::Minute);
+    let ends_at: Instant = now.add_hours(7).unwrap();
+    let no_step = None as Option<i64>;
+    let no_tokens = None as Option<Vec<ManifestBucket>>;
+
+    // NFT set up complete
+
+    let list_manifest = |floor: Decimal| {
+        let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![firstrs.1[0].clone()]);
+        ManifestBuilder::new()
+            .call_method(
+                account_component,
+                "withdraw_non_fungibles",
+                manifest_args!(firstrs.0, vec![firstrs.1[0].clone()]),
+            )
+            .take_non_fungibles_from_worktop(firstrs.0, btree_one, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_function(
+                    package_address,
+                    "DutchListing",
+                    "new_dutch_listing",
+                    manifest_args!(
+                        "Car listing", // String name
+                        no_tokens.clone(),
+                        Some(vec![lookup.bucket("bucket1")]),
+                        payment_token,
+                        dec!(1000),
+                        floor,
+                        ends_at,
+                        no_step
+                    ),
+                )
+            })
+            .call_method(
+                account_component,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // a floor of 100.5 can't be paid in whole tokens || failure
+
+    let receipt_new_listing = test_runner.execute_manifest_ignoring_fee(
+        list_manifest(dec!("100.5")),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_listing.expect_commit_failure();
+
+    let receipt_new_listing = test_runner.execute_manifest_ignoring_fee(
+        list_manifest(dec!(100)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_listing.expect_commit_success();
+    let listing = receipt_new_listing
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(listing, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let seller_key: (ResourceAddress, NonFungibleLocalId) = receipty.expect_commit(true).output(1);
+
+    let take_manifest = |amount: Decimal| {
+        ManifestBuilder::new()
+            .call_method(account_component2, "withdraw", manifest_args!(payment_token, amount))
+            .take_from_worktop(payment_token, amount, "bucket1")
+            .with_name_lookup(|builder, lookup| {
+                builder.call_method(listing, "take_listing", manifest_args!(lookup.bucket("bucket1")))
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // move the clock an hour, 1000 - 900 / 7 = 871.43, which rounds up to 872
+
+    test_runner.advance_to_round_at_timestamp(
+        Round::of(1),
+        (now.seconds_since_unix_epoch + 60 * 60) * 1000,
+    );
+
+    let manifest = ManifestBuilder::new()
+        .call_method(listing, "current_price", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let price: Decimal = receipty.expect_commit(true).output(1);
+    assert_eq!(price, dec!(872));
+
+    // account 2 pays the rounded down price || failure
+
+    let receipt_take = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(dec!(871)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_take.expect_commit_failure();
+
+    let receipt_take = test_runner.execute_manifest_ignoring_fee(
+        take_manifest(dec!(900)),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_take.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, payment_token), dec!(128));
+    assert_eq!(test_runner.get_component_balance(account_component2, firstrs.0), dec!(1));
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            seller_key.0,
+            vec![seller_key.1]
+        ))
+        .call_method(listing, "seller_claim", manifest_args!())
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_seller = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_seller.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component, payment_token), dec!(872));
+}
+
+#[test]
+fn test_partner_key_reclaim() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2 - allocated, so there are no owner_keys to claim the key with
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Create an account 3
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+
+    // NFT set up complete
+
+    // account 2 refuses anything they've not seen before
+
+    let manifest = ManifestBuilder::new()
+        .call_method(
+            account_component2,
+            "set_default_deposit_rule",
+            manifest_args!(DefaultDepositRule::Reject),
+        )
+        .build();
+    let receipt_rule = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_rule.expect_commit_success();
+
+    // account 1 offers 1000 XRD for the car, to account 2 only
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Just for you", // String name
+                    Some(vec![account_component2]),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let localtry = output.1;
+    let partner_badge = output.3.unwrap();
+    let partner_badge_local = output.4[0].clone();
+
+    // the key never made it into account 2
+    assert_eq!(test_runner.get_component_balance(account_component2, partner_badge), dec!(0));
+
+    // account 2 has no owner_keys to claim with || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "partner_claim_key", manifest_args!(account_component2))
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_claim_key = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_claim_key.expect_commit_failure();
+
+    // account 3 isn't the creator || failure
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "creator_reclaim_partner_key", manifest_args!(account_component2))
+        .call_method(
+            account_component3,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_steal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_steal.expect_commit_failure();
+
+    // account 1 reclaims the key and hands it straight to account 2, who signs too
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![localtry]
+        ))
+        .call_method(component, "creator_reclaim_partner_key", manifest_args!(account_component2))
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_reclaim = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![
+            NonFungibleGlobalId::from_public_key(&public_key),
+            NonFungibleGlobalId::from_public_key(&public_key2),
+        ],
+    );
+    receipt_reclaim.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, partner_badge), dec!(1));
+
+    // and takes the swap with it
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            partner_badge,
+            vec![partner_badge_local]
+        ))
+        .pop_from_auth_zone("partner_proof")
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "accept_trade",
+                manifest_args!(vec![lookup.bucket("bucket1")], no_tokens, Some(lookup.proof("partner_proof")), no_referral, None as Option<ManifestProof>)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let taker_before = test_runner.get_component_balance(account_component2, XRD);
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, XRD), taker_before + dec!(1000));
+}
+
+#[test]
+fn test_arbiter_filled_by() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create accounts 1, 2 and 3
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    let (public_key3, _private_key3, account_component3) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 NFTs
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let component = receipt.expect_commit(true).new_component_addresses()[0];
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "local_ids_1", manifest_args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    let secondrs: (ResourceAddress, Vec<NonFungibleLocalId>) =
+        receipt.expect_commit(true).output(1);
+
+    let btree_one: BTreeSet<NonFungibleLocalId> = FromIterator::from_iter(vec![secondrs.1[0].clone()]);
+    let request: Option<Vec<NonFungibleGlobalId>> =
+        Some(vec![NonFungibleGlobalId::new(secondrs.0, secondrs.1[0].clone())]);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_collections = None as Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_receipt = None as Option<ManifestProof>;
+    let arbiter: Option<(ComponentAddress, i64)> = Some((account_component3, 3600));
+
+    // NFT set up complete
+
+    // account 1 offers 1000 XRD for the car, to account 2 only, with account 3 arbitrating
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(1000)))
+        .take_from_worktop(XRD, dec!(1000), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Arbitrated trade", // String name
+                    Some(vec![account_component2]),
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    request.clone(),
+                    no_collections,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions {
+                        arbiter,
+                        ..Default::default()
+                    }
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_badge", manifest_args!())
+        .build();
+    let receipty = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let output: (ResourceAddress, NonFungibleLocalId, NonFungibleGlobalId, Option<ResourceAddress>, Vec<NonFungibleLocalId>) =
+        receipty.expect_commit(true).output(1);
+    let rebadge = output.0;
+    let partner_badge = output.3.unwrap();
+    let partner_badge_local = output.4[0].clone();
+    let arbiter_key = NonFungibleLocalId::string("Trove_Arbiter_Key").unwrap();
+
+    // account 2 deposits the car with their partner key
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component2, "create_proof_of_non_fungibles", manifest_args!(
+            partner_badge,
+            vec![partner_badge_local.clone()]
+        ))
+        .pop_from_auth_zone("partner_proof")
+        .call_method(
+            account_component2,
+            "withdraw_non_fungibles",
+            manifest_args!(secondrs.0, vec![secondrs.1[0].clone()]),
+        )
+        .take_non_fungibles_from_worktop(secondrs.0, btree_one, "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                component,
+                "partner_deposit_nfts",
+                manifest_args!(vec![lookup.bucket("bucket1")], Some(lookup.proof("partner_proof")), no_receipt, no_referral)
+            )
+        })
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_b_deposit = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_deposit.expect_commit_success();
+
+    // account 3 releases the swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component3, "create_proof_of_non_fungibles", manifest_args!(
+            rebadge,
+            vec![arbiter_key]
+        ))
+        .call_method(component, "arbiter_release", manifest_args!())
+        .build();
+    let receipt_release = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key3)],
+    );
+    receipt_release.expect_commit_success();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(component, "get_proposal", manifest_args!())
+        .build();
+    let receipt_view = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    let view: ProposalView = receipt_view.expect_commit(true).output(1);
+    assert_eq!(view.status, SwapStatus::Settled);
+    assert_eq!(view.filled_by, Some(partner_badge_local));
+}
+
+#[test]
+fn test_overlapping_trait_requests() {
+    // Setup the environment
+    let mut test_runner = TestRunnerBuilder::new().without_trace().build();
+    // Create an account
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    // Create an account 2
+    let (public_key2, _private_key2, account_component2) = test_runner.new_allocated_account();
+    // Publish package
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // account 2 gets a Raptor (#1#) and a Mustang (#2#), both Fords
+
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "Bootstrap", "bootstrap_fleet", manifest_args!())
+        .call_method(
+            account_component2,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt.expect_commit_success();
+    let fleet = receipt.expect_commit(true).new_resource_addresses()[0];
+
+    let raptor = NonFungibleLocalId::integer(1);
+    let mustang = NonFungibleLocalId::integer(2);
+    let none_hashmap_fungibles: Option<HashMap<ResourceAddress, Decimal>> = None as Option<HashMap<ResourceAddress, Decimal>>;
+    let no_registry = None as Option<ComponentAddress>;
+    let no_referral = None as Option<(ComponentAddress, u16)>;
+    let no_nft_request = None as Option<Vec<NonFungibleGlobalId>>;
+    // field 0 on the Car is the name, field 1 the manufacturer - the Ford request comes first, so taking the
+    // first fit would hand it the Raptor and leave nothing for the Raptor request
+    let requests: Option<Vec<(ResourceAddress, u64, Vec<(u32, String)>)>> = Some(vec![
+        (fleet, 1, vec![(1u32, "Ford".to_string())]),
+        (fleet, 1, vec![(0u32, "Raptor".to_string())]),
+    ]);
+    let partner_option = None as Option<Vec<ComponentAddress>>;
+    let no_nfts = None as Option<Vec<ManifestBucket>>;
+    let no_tokens: Vec<ManifestBucket> = Vec::new();
+    let partner_badge = None as Option<ManifestProof>;
+
+    // account 1 creates the swap
+
+    let manifest = ManifestBuilder::new()
+        .call_method(account_component, "withdraw", manifest_args!(XRD, dec!(100)))
+        .take_from_worktop(XRD, dec!(100), "bucket1")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_function(
+                package_address,
+                "Barter",
+                "new_trade_proposal",
+                manifest_args!(
+                    "Two Fords", // String name
+                    partner_option,
+                    Some(vec![lookup.bucket("bucket1")]),
+                    no_nfts,
+                    no_nft_request,
+                    requests,
+                    none_hashmap_fungibles,
+                    no_registry,
+                    ProposalOptions::default()
+                ),
+            )
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt_new_trade_proposal = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    receipt_new_trade_proposal.expect_commit_success();
+    let component = receipt_new_trade_proposal
+        .expect_commit(true).new_component_addresses()[0];
+
+    // one bucket per car, in the order given
+
+    let accept_manifest = |cars: Vec<NonFungibleLocalId>| {
+        let mut builder = ManifestBuilder::new();
+        let mut names: Vec<String> = Vec::new();
+        for (index, car) in cars.into_iter().enumerate() {
+            let name = format!("bucket{}", index);
+            builder = builder
+                .call_method(
+                    account_component2,
+                    "withdraw_non_fungibles",
+                    manifest_args!(fleet, vec![car.clone()]),
+                )
+                .take_non_fungibles_from_worktop(fleet, BTreeSet::from([car]), name.as_str());
+            names.push(name);
+        }
+        builder
+            .with_name_lookup(|builder, lookup| {
+                let buckets: Vec<ManifestBucket> = names.iter().map(|name| lookup.bucket(name.as_str())).collect();
+                builder.call_method(
+                    component,
+                    "accept_trade",
+                    manifest_args!(buckets, no_tokens.clone(), partner_badge, no_referral, None as Option<ManifestProof>)
+                )
+            })
+            .call_method(
+                account_component2,
+                "deposit_batch",
+                manifest_args!(ManifestExpression::EntireWorktop),
+            )
+            .build()
+    };
+
+    // the Raptor alone only fills one of the two || failure
+
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        accept_manifest(vec![raptor.clone()]),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_failure();
+
+    let receipt_b_accept = test_runner.execute_manifest_ignoring_fee(
+        accept_manifest(vec![raptor, mustang]),
+        vec![NonFungibleGlobalId::from_public_key(&public_key2)],
+    );
+    receipt_b_accept.expect_commit_success();
+    assert_eq!(test_runner.get_component_balance(account_component2, fleet), dec!(0));
+}